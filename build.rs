@@ -1,4 +1,5 @@
 extern crate pkg_config;
+extern crate cc;
 use std::env;
 use std::process::Command;
 use std::path::*;
@@ -17,5 +18,23 @@ fn main() {
     println!("cargo:rustc-link-lib=static=DOtherSideStatic");
     println!("cargo:rustc-link-lib=dylib=stdc++");
 
-    Config::new().probe("Qt5Core Qt5Gui Qt5Qml Qt5Quick Qt5Widgets").unwrap();
+    let qt = Config::new().probe("Qt5Core Qt5Gui Qt5Qml Qt5Quick Qt5Widgets").unwrap();
+
+    // Additions to DOtherSide API, see `ext/dosext.cpp`
+    let mut ext = cc::Build::new();
+    ext.cpp(true)
+        .flag_if_supported("-std=c++11")
+        .file("ext/dosext.cpp");
+    for include in &qt.include_paths {
+        ext.include(include);
+        // Private headers, e.g. `QtQml/private/qv4engine_p.h`, lie in a directory named after the Qt version
+        for module in &["QtCore", "QtQml"] {
+            let private = include.join(module).join(&qt.version);
+            if private.exists() {
+                ext.include(&private).include(private.join(module));
+            }
+        }
+    }
+    ext.compile("dosext");
+    println!("cargo:rerun-if-changed=ext/dosext.cpp");
 }
@@ -10,9 +10,8 @@ use qml::*;
 pub struct Test;
 
 impl QTest {
-    fn assure_everything_okay(&mut self) -> Option<&QVariant> {
+    fn assure_everything_okay(&mut self) {
         println!("It's okay");
-        None
     }
 }
 
@@ -0,0 +1,32 @@
+import QtQuick 2.2
+import QtQuick.Controls 1.2
+import QtQuick.Layouts 1.1
+import QtQuick.Window 2.1
+
+ApplicationWindow {
+  width: 400
+  height: 300
+  title: counter.title
+  Component.onCompleted: visible = true
+
+  ColumnLayout {
+    anchors.fill: parent
+    Text {
+      id: text
+      anchors.horizontalCenter: parent.horizontalCenter
+      text: counter.describe("Counted to")
+    }
+    Button {
+      anchors.horizontalCenter: parent.horizontalCenter
+      text: "Count by " + counter.step
+      onClicked: {
+        counter.increment()
+        text.text = counter.describe("Counted to")
+      }
+    }
+    Connections {
+      target: counter
+      onOverflow: text.text = "Overflowed at " + at
+    }
+  }
+}
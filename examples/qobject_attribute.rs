@@ -0,0 +1,49 @@
+extern crate qml;
+
+use qml::*;
+
+pub struct Counter {
+    count: i32,
+    step: i32,
+}
+
+#[qobject(Counter)]
+impl QCounter {
+    #[qsignal]
+    fn overflow(&self, at: i32);
+
+    #[qslot]
+    fn increment(&mut self) -> i32 {
+        let count = self.count + self.step;
+        if count > 10 {
+            self.overflow(count);
+            self.set_count(0);
+        } else {
+            self.set_count(count);
+        }
+        self.count
+    }
+
+    #[qslot]
+    fn describe(&self, prefix: String) -> String {
+        format!("{} {} by {}", prefix, self.count, self.step)
+    }
+
+    #[qmember(write, notify)]
+    fn count(&self) -> i32;
+
+    #[qmember]
+    fn step(&self) -> i32;
+
+    #[qproperty]
+    fn title(&self) -> String;
+}
+
+fn main() {
+    let mut qqae = QmlEngine::new();
+    let counter = QCounter::new(Counter { count: 0, step: 3 }, "Counter".into());
+    qqae.set_and_store_property("counter", counter.get_qobj());
+    qqae.load_file("examples/qobject_attribute.qml");
+    qqae.exec();
+    qqae.quit();
+}
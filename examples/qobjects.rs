@@ -6,9 +6,8 @@ use qml::*;
 pub struct Test;
 
 impl Test {
-    pub fn launchGoose(&self, i: i32, i2: String) -> Option<&QVariant> {
+    pub fn launchGoose(&self, i: i32, i2: String) {
         println!("GOOSE HI from {} and {}", i2, i);
-        None
     }
 }
 
@@ -25,7 +24,7 @@ fn main() {
     let mut qqae = QmlEngine::new();
     let mut qtest = QTest::new(Test);
     qtest.testname(54, 55);
-    qtest.qslot_call("launchGoose",
-                     vec![42.into(), "QML Rust".to_string().into()]);
+    let args: Vec<QVariant> = vec![42.into(), "QML Rust".to_string().into()];
+    qtest.qslot_call("launchGoose", args.iter().map(Into::into).collect()).unwrap();
     println!("{:?}", qtest.qmeta());
 }
@@ -19,10 +19,9 @@ pub Test as QTest{
 });
 
 impl QTest {
-    pub fn click(&self) -> Option<&QVariant> {
+    pub fn click(&self) {
         println!("IT CLICKED");
         self.updateText("Woah, Rust has noticed you".into());
-        None
     }
 }
 
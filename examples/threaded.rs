@@ -8,12 +8,12 @@ use qml::*;
 pub struct Logic;
 
 impl QLogic {
-    pub fn downloadPage(&mut self, url: String) -> Option<&QVariant> {
-        self.threaded(|s| {
-            thread::sleep(Duration::from_secs(2));;
-            s.pageDownloaded(url);
+    pub fn downloadPage(&mut self, url: String) {
+        let handle = self.handle();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(2));
+            handle.queue(move |s: &mut QLogic| s.pageDownloaded(url));
         });
-        None
     }
 }
 
@@ -0,0 +1,380 @@
+// Small additions to the DOtherSide C API, used by qml-rust where DOtherSide
+// does not expose the required part of Qt.
+//
+// Every pointer crossing this boundary is the same pointer DOtherSide hands out,
+// i.e. `DosQVariant *` is a `QVariant *` and `DosQObject *` is a `QObject *`.
+// Strings returned from here are allocated with `qstrdup` and should be freed
+// with `dos_chararray_delete`.
+
+#include <QtCore/QByteArray>
+#include <QtCore/QDateTime>
+#include <QtCore/QJsonArray>
+#include <QtCore/QJsonDocument>
+#include <QtCore/QJsonObject>
+#include <QtCore/QJsonValue>
+#include <QtCore/QMap>
+#include <QtCore/QMetaMethod>
+#include <QtCore/QObject>
+#include <QtCore/QPointF>
+#include <QtCore/QRectF>
+#include <QtCore/QSizeF>
+#include <QtCore/QString>
+#include <QtCore/QVariant>
+#include <QtGui/QColor>
+#include <QtQml/QJSEngine>
+#include <QtQml/private/qv4engine_p.h>
+
+#include <algorithm>
+
+// Gives access to the protected QObject::senderSignalIndex(), which tells whether a slot is called by a signal.
+struct SenderAccess : public QObject
+{
+    static int senderSignalIndex(QObject *object)
+    {
+        return static_cast<SenderAccess *>(object)->QObject::senderSignalIndex();
+    }
+};
+
+extern "C" {
+
+struct DosExtQVariantMap
+{
+    int size;
+    char **keys;
+    void **values;
+};
+
+int dosext_qvariant_userType(const void *vptr)
+{
+    return static_cast<const QVariant *>(vptr)->userType();
+}
+
+char *dosext_qvariant_typeName(const void *vptr)
+{
+    const char *name = static_cast<const QVariant *>(vptr)->typeName();
+    return qstrdup(name ? name : "");
+}
+
+bool dosext_qvariant_isValid(const void *vptr)
+{
+    return static_cast<const QVariant *>(vptr)->isValid();
+}
+
+void *dosext_qvariant_create_null()
+{
+    return new QVariant(QVariant::fromValue(nullptr));
+}
+
+bool dosext_qvariant_equal(const void *vptr, const void *other)
+{
+    return *static_cast<const QVariant *>(vptr) == *static_cast<const QVariant *>(other);
+}
+
+void *dosext_qvariant_create_schar(signed char value)
+{
+    return new QVariant(QVariant::fromValue(value));
+}
+
+void *dosext_qvariant_create_short(short value)
+{
+    return new QVariant(QVariant::fromValue(value));
+}
+
+void *dosext_qvariant_create_uchar(unsigned char value)
+{
+    return new QVariant(QVariant::fromValue(value));
+}
+
+void *dosext_qvariant_create_ushort(unsigned short value)
+{
+    return new QVariant(QVariant::fromValue(value));
+}
+
+void *dosext_qvariant_create_uint(unsigned int value)
+{
+    return new QVariant(value);
+}
+
+void *dosext_qvariant_create_longlong(long long value)
+{
+    return new QVariant(value);
+}
+
+void *dosext_qvariant_create_ulonglong(unsigned long long value)
+{
+    return new QVariant(value);
+}
+
+unsigned int dosext_qvariant_toUInt(const void *vptr)
+{
+    return static_cast<const QVariant *>(vptr)->toUInt();
+}
+
+long long dosext_qvariant_toLongLong(const void *vptr)
+{
+    return static_cast<const QVariant *>(vptr)->toLongLong();
+}
+
+unsigned long long dosext_qvariant_toULongLong(const void *vptr)
+{
+    return static_cast<const QVariant *>(vptr)->toULongLong();
+}
+
+void *dosext_qvariant_create_bytearray(const char *data, int size)
+{
+    return new QVariant(QByteArray(data, size));
+}
+
+// Returns a copy of the stored bytes, which should be freed with `dos_chararray_delete`.
+char *dosext_qvariant_toByteArray(const void *vptr, int *size)
+{
+    const QByteArray bytes = static_cast<const QVariant *>(vptr)->toByteArray();
+    *size = bytes.size();
+    char *result = new char[bytes.size()];
+    std::copy(bytes.constBegin(), bytes.constEnd(), result);
+    return result;
+}
+
+void *dosext_qvariant_create_date(int year, int month, int day)
+{
+    return new QVariant(QDate(year, month, day));
+}
+
+void *dosext_qvariant_create_time(int hour, int minute, int second, int msec)
+{
+    return new QVariant(QTime(hour, minute, second, msec));
+}
+
+void *dosext_qvariant_create_datetime(int year, int month, int day,
+                                      int hour, int minute, int second, int msec,
+                                      int offsetFromUtc)
+{
+    return new QVariant(QDateTime(QDate(year, month, day),
+                                  QTime(hour, minute, second, msec),
+                                  Qt::OffsetFromUTC, offsetFromUtc));
+}
+
+bool dosext_qvariant_toDate(const void *vptr, int *year, int *month, int *day)
+{
+    const QDate date = static_cast<const QVariant *>(vptr)->toDate();
+    if (!date.isValid())
+        return false;
+    date.getDate(year, month, day);
+    return true;
+}
+
+bool dosext_qvariant_toTime(const void *vptr, int *hour, int *minute, int *second, int *msec)
+{
+    const QTime time = static_cast<const QVariant *>(vptr)->toTime();
+    if (!time.isValid())
+        return false;
+    *hour = time.hour();
+    *minute = time.minute();
+    *second = time.second();
+    *msec = time.msec();
+    return true;
+}
+
+bool dosext_qvariant_toDateTime(const void *vptr, int *year, int *month, int *day,
+                                int *hour, int *minute, int *second, int *msec,
+                                int *offsetFromUtc)
+{
+    const QDateTime dateTime = static_cast<const QVariant *>(vptr)->toDateTime();
+    if (!dateTime.isValid())
+        return false;
+    dateTime.date().getDate(year, month, day);
+    const QTime time = dateTime.time();
+    *hour = time.hour();
+    *minute = time.minute();
+    *second = time.second();
+    *msec = time.msec();
+    *offsetFromUtc = dateTime.offsetFromUtc();
+    return true;
+}
+
+void *dosext_qvariant_create_pointf(double x, double y)
+{
+    return new QVariant(QPointF(x, y));
+}
+
+void *dosext_qvariant_create_sizef(double width, double height)
+{
+    return new QVariant(QSizeF(width, height));
+}
+
+void *dosext_qvariant_create_rectf(double x, double y, double width, double height)
+{
+    return new QVariant(QRectF(x, y, width, height));
+}
+
+void dosext_qvariant_toPointF(const void *vptr, double *x, double *y)
+{
+    const QPointF point = static_cast<const QVariant *>(vptr)->toPointF();
+    *x = point.x();
+    *y = point.y();
+}
+
+void dosext_qvariant_toSizeF(const void *vptr, double *width, double *height)
+{
+    const QSizeF size = static_cast<const QVariant *>(vptr)->toSizeF();
+    *width = size.width();
+    *height = size.height();
+}
+
+void dosext_qvariant_toRectF(const void *vptr, double *x, double *y, double *width, double *height)
+{
+    const QRectF rect = static_cast<const QVariant *>(vptr)->toRectF();
+    *x = rect.x();
+    *y = rect.y();
+    *width = rect.width();
+    *height = rect.height();
+}
+
+void *dosext_qvariant_create_color(int red, int green, int blue, int alpha)
+{
+    return new QVariant(QColor(red, green, blue, alpha));
+}
+
+// Accepts both a QColor and a color name, e.g. "red" or "#ff0000", as QML does.
+bool dosext_qvariant_toColor(const void *vptr, int *red, int *green, int *blue, int *alpha)
+{
+    const QVariant *variant = static_cast<const QVariant *>(vptr);
+    const QColor color = variant->userType() == QMetaType::QString
+        ? QColor(variant->toString())
+        : variant->value<QColor>();
+    if (!color.isValid())
+        return false;
+    color.getRgb(red, green, blue, alpha);
+    return true;
+}
+
+// QJsonDocument only handles objects and arrays at the top level,
+// so any JSON value is wrapped into a single-element array on the way.
+void *dosext_qvariant_create_json(const char *json, int size)
+{
+    QByteArray data("[");
+    data.append(json, size);
+    data.append("]");
+    const QJsonDocument document = QJsonDocument::fromJson(data);
+    if (!document.isArray() || document.array().size() != 1)
+        return new QVariant();
+    return new QVariant(document.array().first());
+}
+
+// Returns nullptr, if the value can't be represented in JSON.
+char *dosext_qvariant_toJson(const void *vptr)
+{
+    const QVariant *variant = static_cast<const QVariant *>(vptr);
+    QJsonValue value;
+    switch (variant->userType()) {
+    case QMetaType::QJsonValue:
+        value = variant->toJsonValue();
+        break;
+    case QMetaType::QJsonObject:
+        value = variant->toJsonObject();
+        break;
+    case QMetaType::QJsonArray:
+        value = variant->toJsonArray();
+        break;
+    case QMetaType::QJsonDocument: {
+        const QJsonDocument document = variant->toJsonDocument();
+        value = document.isArray() ? QJsonValue(document.array()) : QJsonValue(document.object());
+        break;
+    }
+    default:
+        value = QJsonValue::fromVariant(*variant);
+    }
+    if (value.isUndefined())
+        return nullptr;
+    const QByteArray data = QJsonDocument(QJsonArray() << value).toJson(QJsonDocument::Compact);
+    // Stripping the wrapping array
+    return qstrdup(data.mid(1, data.size() - 2).constData());
+}
+
+void *dosext_qvariant_create_map(int size, const char **keys, const void **values)
+{
+    QVariantMap map;
+    for (int i = 0; i < size; ++i)
+        map.insert(QString::fromUtf8(keys[i]), *static_cast<const QVariant *>(values[i]));
+    return new QVariant(map);
+}
+
+DosExtQVariantMap *dosext_qvariant_toMap(const void *vptr)
+{
+    const QVariantMap map = static_cast<const QVariant *>(vptr)->toMap();
+    DosExtQVariantMap *result = new DosExtQVariantMap();
+    result->size = map.size();
+    result->keys = new char *[map.size()];
+    result->values = new void *[map.size()];
+    int i = 0;
+    for (QVariantMap::const_iterator it = map.cbegin(); it != map.cend(); ++it, ++i) {
+        result->keys[i] = qstrdup(it.key().toUtf8().constData());
+        result->values[i] = new QVariant(it.value());
+    }
+    return result;
+}
+
+void dosext_qvariantmap_delete(DosExtQVariantMap *ptr)
+{
+    if (!ptr)
+        return;
+    for (int i = 0; i < ptr->size; ++i) {
+        delete[] ptr->keys[i];
+        delete static_cast<QVariant *>(ptr->values[i]);
+    }
+    delete[] ptr->keys;
+    delete[] ptr->values;
+    delete ptr;
+}
+
+// Throws a JavaScript `Error` in the engine, which called a slot of the object.
+// Returns false if the slot is not called from JavaScript: the object is not exposed
+// to any engine, no JavaScript function is running, or the slot is called by a signal.
+// An error thrown then would stay pending until some unrelated JavaScript code runs.
+bool dosext_qobject_throwError(void *vptr, const char *message)
+{
+    QObject *object = static_cast<QObject *>(vptr);
+    QJSEngine *engine = qjsEngine(object);
+    if (!engine || !engine->handle()->currentStackFrame || SenderAccess::senderSignalIndex(object) != -1)
+        return false;
+    engine->throwError(QString::fromUtf8(message));
+    return true;
+}
+
+// Marks a property of the object's class as CONSTANT, which DOtherSide can't declare.
+// The meta object is built by QMetaObjectBuilder in writable memory and is patched in place,
+// so this should be done before QML sees the class. Returns false if there is no such property
+// or the layout of the meta object is unknown.
+bool dosext_qobject_setPropertyConstant(void *vptr, const char *name)
+{
+    const QMetaObject *meta = static_cast<QObject *>(vptr)->metaObject();
+    const int index = meta->indexOfProperty(name);
+    // Qt 5 meta objects of revisions 7 and 8 start with: revision, className, classInfoCount,
+    // classInfoData, methodCount, methodData, propertyCount, propertyData.
+    // Every property takes three fields: name, type and flags.
+    uint *data = const_cast<uint *>(meta->d.data);
+    if (index < meta->propertyOffset() || data[0] < 7 || data[0] > 8)
+        return false;
+    const uint Constant = 0x00000400; // PropertyFlags::Constant in qmetaobject_p.h
+    data[data[7] + 3 * (index - meta->propertyOffset()) + 2] |= Constant;
+    return true;
+}
+
+// Returns the signature of the first signal or slot with the given name in the form
+// `SIGNAL()` or `SLOT()` produces, or nullptr if the object has no such method.
+char *dosext_qobject_methodSignature(const void *vptr, const char *name, bool signal)
+{
+    const QMetaObject *meta = static_cast<const QObject *>(vptr)->metaObject();
+    const QMetaMethod::MethodType type = signal ? QMetaMethod::Signal : QMetaMethod::Slot;
+    for (int i = 0; i < meta->methodCount(); ++i) {
+        const QMetaMethod method = meta->method(i);
+        if (method.methodType() == type && method.name() == name) {
+            const QByteArray signature = QByteArray::number(signal ? QSIGNAL_CODE : QSLOT_CODE)
+                                         + method.methodSignature();
+            return qstrdup(signature.constData());
+        }
+    }
+    return nullptr;
+}
+
+}
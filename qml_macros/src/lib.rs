@@ -0,0 +1,98 @@
+//! Procedural macros for [qml-rust](https://github.com/White-Oak/qml-rust).
+//!
+//! Use them through the `derive` feature of the `qml` crate, which re-exports everything from here.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, Ident, ItemImpl};
+
+mod qobject;
+mod variant;
+
+/// Reports errors with `compile_error!` from the prelude, as `::core` can't be named in 2015 edition crates.
+fn compile_errors(errors: syn::Error) -> proc_macro2::TokenStream {
+    let errors = errors.into_iter().map(|e| {
+        let message = e.to_string();
+        quote_spanned!(e.span()=> compile_error!(#message);)
+    });
+    quote!(#(#errors)*)
+}
+
+/// Implements `From<T> for QVariant` and `QMetaTypable`.
+///
+/// * Structs with named fields become a `QVariantMap`, an object in QML, with field names as keys.
+/// * Tuple structs become a `QVariantList`, an array in QML.
+/// * C-like enums become an `int` with the discriminant of a variant, or a `QString`
+///   with the name of a variant if the enum is marked with `#[qml(string)]`.
+///
+/// Fields and variants can be renamed with `#[qml(rename = "name")]`.
+#[proc_macro_derive(ToQVariant, attributes(qml))]
+pub fn derive_to_qvariant(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    variant::expand_to_qvariant(&input)
+        .unwrap_or_else(compile_errors)
+        .into()
+}
+
+/// Implements `TryFrom<&QVariant>` and `TryFrom<QVariant>`, the reverse of [`ToQVariant`](derive.ToQVariant.html).
+///
+/// Missing keys of a `QVariantMap` are read as `undefined`, so `Option` fields may be omitted in QML,
+/// while other fields fail with `QVariantConversionError::MissingKey`.
+#[proc_macro_derive(FromQVariant, attributes(qml))]
+pub fn derive_from_qvariant(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    variant::expand_from_qvariant(&input)
+        .unwrap_or_else(compile_errors)
+        .into()
+}
+
+/// Generates a `QObject` wrapper from an `impl` block, an alternative to the `Q_OBJECT!` macro.
+///
+/// The argument is the wrapped type, and the type of the `impl` block is the name of the wrapper to generate.
+/// Items of the block are marked with:
+///
+/// * `#[qslot]` for slots, which are methods with a body, returning the same types as in `Q_OBJECT!`;
+/// * `#[qsignal]` for signals, which are declared without a body: `fn clicked(&self, x: i32);`;
+/// * `#[qproperty]` for properties, declared as `fn name(&self) -> String;`. Names of the read and write slots
+///   and of the notify signal default to `get_name`, `set_name` and `name_changed`, and can be changed
+///   with `#[qproperty(read = .., write = .., notify = ..)]`;
+/// * `#[qmember]` for properties backed by fields of the wrapped type, declared as `fn count(&self) -> i32;`
+///   for a field `count`. A member has only the `get_count` read slot, unless `#[qmember(write, notify)]` adds
+///   `set_count` and `count_changed`. Names can be changed like for `#[qproperty]`, e.g. `write = update_count`.
+///
+/// Other items are left untouched. Like `Q_OBJECT!`, the generated code expects `qml::*` to be imported.
+/// Types of arguments, return values and properties are checked where they are declared.
+///
+/// A complete program is in `examples/qobject_attribute.rs`.
+///
+/// ```ignore
+/// #[qobject(Example)]
+/// impl QExample {
+///     #[qsignal]
+///     fn simple_signal(&self, s: String);
+///
+///     #[qslot]
+///     fn simple_receiver(&mut self, x: i32) -> String {
+///         x.to_string()
+///     }
+///
+///     #[qproperty]
+///     fn name(&self) -> String;
+///
+///     #[qmember(write, notify)]
+///     fn count(&self) -> i32;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn qobject(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let origin = syn::parse_macro_input!(attr as Ident);
+    let input = syn::parse_macro_input!(item as ItemImpl);
+    qobject::expand_qobject(origin, input)
+        .unwrap_or_else(compile_errors)
+        .into()
+}
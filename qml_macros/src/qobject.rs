@@ -0,0 +1,592 @@
+use proc_macro2::TokenStream;
+use syn::{Attribute, FnArg, Ident, ImplItem, ItemImpl, Meta, Pat, ReturnType, Result, Signature, Token, TraitItemFn,
+          Type};
+use syn::spanned::Spanned;
+
+/// Kind of an item in a `#[qobject]` impl block, marked with an attribute.
+enum Marker {
+    Slot,
+    Signal,
+    Property(Box<Attribute>),
+    Member(Box<Attribute>),
+}
+
+/// Names of the read and write slots and of the notify signal of a property or a member.
+struct Accessors {
+    read: Ident,
+    write: Option<Ident>,
+    notify: Option<Ident>,
+}
+
+fn take_marker(attrs: &mut Vec<Attribute>) -> Result<Option<Marker>> {
+    let mut marker = None;
+    let mut res = Ok(());
+    attrs.retain(|attr| {
+        let found = if attr.path().is_ident("qslot") {
+            Marker::Slot
+        } else if attr.path().is_ident("qsignal") {
+            Marker::Signal
+        } else if attr.path().is_ident("qproperty") {
+            Marker::Property(Box::new(attr.clone()))
+        } else if attr.path().is_ident("qmember") {
+            Marker::Member(Box::new(attr.clone()))
+        } else {
+            return true;
+        };
+        if marker.is_some() {
+            res = Err(syn::Error::new_spanned(attr, "an item can be only one of a slot, a signal, a property or a member"));
+        }
+        marker = Some(found);
+        false
+    });
+    res.map(|_| marker)
+}
+
+/// Returns names and types of arguments, checking that the function takes `&self` or `&mut self`.
+fn arguments(sig: &Signature) -> Result<Vec<(Ident, Type)>> {
+    match sig.receiver() {
+        Some(receiver) if receiver.reference.is_some() => {}
+        _ => return Err(syn::Error::new_spanned(sig, "expected a method taking `&self` or `&mut self`")),
+    }
+    let mut args = Vec::new();
+    for arg in sig.inputs.iter().skip(1) {
+        if let FnArg::Typed(ref arg) = *arg {
+            match *arg.pat {
+                Pat::Ident(ref pat) => args.push((pat.ident.clone(), (*arg.ty).clone())),
+                _ => return Err(syn::Error::new_spanned(&arg.pat, "expected a plain identifier")),
+            }
+        }
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.generics, "slots and signals can't be generic"));
+    }
+    Ok(args)
+}
+
+/// Parses `#[qproperty(read = .., write = .., notify = ..)]` or `#[qmember(..)]`, every part is optional.
+///
+/// A property always has all three, named `get_name`, `set_name` and `name_changed` by default. A member has only
+/// a read slot by default, while `write` and `notify` without a name add a slot or a signal with the default name.
+fn accessors(attr: &Attribute, name: &Ident, member: bool) -> Result<Accessors> {
+    let default_write = Ident::new(&format!("set_{}", name), name.span());
+    let default_notify = Ident::new(&format!("{}_changed", name), name.span());
+    let mut res = Accessors {
+        read: Ident::new(&format!("get_{}", name), name.span()),
+        write: if member { None } else { Some(default_write.clone()) },
+        notify: if member { None } else { Some(default_notify.clone()) },
+    };
+    if let Meta::List(_) = attr.meta {
+        attr.parse_nested_meta(|meta| {
+            let value = if meta.input.peek(Token![=]) {
+                Some(meta.value()?.parse::<Ident>()?)
+            } else {
+                None
+            };
+            if meta.path.is_ident("read") {
+                res.read = value.ok_or_else(|| meta.error("expected a name of the read slot: `read = name`"))?;
+            } else if meta.path.is_ident("write") {
+                res.write = Some(value.unwrap_or_else(|| default_write.clone()));
+            } else if meta.path.is_ident("notify") {
+                res.notify = Some(value.unwrap_or_else(|| default_notify.clone()));
+            } else if member {
+                return Err(meta.error("unknown qmember attribute, expected `read`, `write` or `notify`"));
+            } else {
+                return Err(meta.error("unknown qproperty attribute, expected `read`, `write` or `notify`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(res)
+}
+
+pub fn expand_qobject(origin: Ident, mut input: ItemImpl) -> Result<TokenStream> {
+    let wrapper = match *input.self_ty {
+        Type::Path(ref path) if path.qself.is_none() && path.path.get_ident().is_some() => {
+            path.path.get_ident().unwrap().clone()
+        }
+        _ => return Err(syn::Error::new_spanned(&input.self_ty, "expected a name of a wrapper to generate")),
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "a qobject can't be generic"));
+    }
+    if let Some((_, ref path, _)) = input.trait_ {
+        return Err(syn::Error::new_spanned(path, "expected an inherent impl block"));
+    }
+
+    let mut signals = Vec::new();
+    let mut slots = Vec::new();
+    let mut properties = Vec::new();
+    let mut members = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut items = Vec::new();
+
+    for item in input.items.drain(..) {
+        let res = match item {
+            ImplItem::Fn(mut f) => {
+                match take_marker(&mut f.attrs) {
+                    Ok(Some(Marker::Slot)) => {
+                        arguments(&f.sig).map(|args| {
+                            slots.push((f.sig.ident.clone(), args, f.sig.output.clone()));
+                            items.push(ImplItem::Fn(f));
+                        })
+                    }
+                    Ok(Some(_)) => {
+                        Err(syn::Error::new_spanned(&f.block,
+                                                    "signals, properties and members are generated, remove the body"))
+                    }
+                    Ok(None) => {
+                        items.push(ImplItem::Fn(f));
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            // Functions without a body are not valid in an impl block, so they aren't parsed as `ImplItem::Fn`
+            ImplItem::Verbatim(tokens) => {
+                match syn::parse2::<TraitItemFn>(tokens.clone()) {
+                    Ok(mut f) => {
+                        match take_marker(&mut f.attrs) {
+                            Ok(Some(Marker::Signal)) => {
+                                match f.sig.output {
+                                    ReturnType::Default => {
+                                        arguments(&f.sig).map(|args| signals.push((f.sig.ident.clone(), args)))
+                                    }
+                                    ReturnType::Type(..) => {
+                                        Err(syn::Error::new_spanned(&f.sig.output, "signals can't return a value"))
+                                    }
+                                }
+                            }
+                            Ok(Some(Marker::Property(attr))) => {
+                                match f.sig.output {
+                                    ReturnType::Type(_, ref ty) if f.sig.inputs.len() == 1 => {
+                                        let name = f.sig.ident.clone();
+                                        accessors(&attr, &name, false).and_then(|accessors| {
+                                            arguments(&f.sig)?;
+                                            properties.push((name, (**ty).clone(), accessors));
+                                            Ok(())
+                                        })
+                                    }
+                                    _ => {
+                                        Err(syn::Error::new_spanned(&f.sig,
+                                                                    "expected a property like `fn name(&self) -> Type;`"))
+                                    }
+                                }
+                            }
+                            Ok(Some(Marker::Member(attr))) => {
+                                match f.sig.output {
+                                    ReturnType::Type(_, ref ty) if f.sig.inputs.len() == 1 => {
+                                        let name = f.sig.ident.clone();
+                                        accessors(&attr, &name, true).and_then(|accessors| {
+                                            arguments(&f.sig)?;
+                                            members.push((name, (**ty).clone(), accessors));
+                                            Ok(())
+                                        })
+                                    }
+                                    _ => {
+                                        Err(syn::Error::new_spanned(&f.sig,
+                                                                    "expected a member like `fn field(&self) -> Type;`"))
+                                    }
+                                }
+                            }
+                            Ok(Some(Marker::Slot)) => Err(syn::Error::new(f.sig.span(), "a slot should have a body")),
+                            Ok(None) => {
+                                Err(syn::Error::new(f.sig.span(), "expected `#[qsignal]`, `#[qproperty]` or `#[qmember]`"))
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Err(_) => {
+                        items.push(ImplItem::Verbatim(tokens));
+                        Ok(())
+                    }
+                }
+            }
+            item => {
+                items.push(item);
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            match errors {
+                Some(ref mut errors) => errors.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    input.items = items;
+    let mut assertions = Vec::new();
+    for (_, args) in &signals {
+        for (_, ty) in args {
+            assertions.push(quote_spanned!(ty.span()=> signal_argument::<#ty>();));
+        }
+    }
+    for (_, args, output) in &slots {
+        for (_, ty) in args {
+            assertions.push(quote_spanned!(ty.span()=> slot_argument::<#ty>();));
+        }
+        if let ReturnType::Type(_, ref ty) = *output {
+            assertions.push(quote_spanned!(ty.span()=> slot_return::<#ty>();));
+        }
+    }
+    for (_, ty, _) in &properties {
+        assertions.push(quote_spanned!(ty.span()=> property::<#ty>();));
+    }
+    for (_, ty, accessors) in &members {
+        if accessors.write.is_some() {
+            assertions.push(quote_spanned!(ty.span()=> writable_member::<#ty>();));
+        } else {
+            assertions.push(quote_spanned!(ty.span()=> member::<#ty>();));
+        }
+    }
+
+    let signals = signals.iter().map(|(name, args)| {
+        let (arg_names, arg_types): (Vec<_>, Vec<_>) = args.iter().cloned().unzip();
+        quote!(fn #name(#(#arg_names: #arg_types),*);)
+    });
+    let slots = slots.iter().map(|(name, args, output)| {
+        let (arg_names, arg_types): (Vec<_>, Vec<_>) = args.iter().cloned().unzip();
+        quote!(fn #name(#(#arg_names: #arg_types),*) #output;)
+    });
+    let members = members.iter().map(|(name, ty, Accessors { read, write, notify })| {
+        let write = write.iter();
+        let notify = notify.iter();
+        quote!(self.#name: #ty; read: #read #(, write: #write)* #(, notify: #notify)*;)
+    });
+    let properties = properties.iter().map(|(name, ty, Accessors { read, write, notify })| {
+        quote!(#name: #ty; read: #read, write: #write, notify: #notify;)
+    });
+
+    Ok(quote! {
+        Q_OBJECT!(
+        pub #origin as #wrapper{
+            signals:
+                #(#signals)*
+            slots:
+                #(#slots)*
+            members:
+                #(#members)*
+            properties:
+                #(#properties)*
+        });
+
+        // Checks types where they are declared, rather than inside of `Q_OBJECT!`
+        #[allow(dead_code)]
+        const _: () = {
+            type Conversion = ::qml::QVariantConversionError;
+
+            fn signal_argument<T: ::qml::QMetaTypable + ::std::convert::Into<::qml::QVariant>>() {}
+            fn slot_argument<T>()
+                where T: ::qml::QMetaTypable + for<'a> ::std::convert::TryFrom<&'a ::qml::QVariant, Error = Conversion>
+            {
+            }
+            fn slot_return<T: ::qml::QSlotReturn + ::qml::QMetaTypable>() {}
+            fn property<T>()
+                where T: ::qml::QMetaTypable + ::std::default::Default + ::std::convert::Into<::qml::QVariant>
+                    + for<'a> ::std::convert::TryFrom<&'a ::qml::QVariant, Error = Conversion>
+            {
+            }
+            fn member<T>()
+                where T: ::qml::QMetaTypable + ::std::clone::Clone + ::std::cmp::PartialEq
+                    + ::std::convert::Into<::qml::QVariant>
+            {
+            }
+            fn writable_member<T>()
+                where T: ::qml::QMetaTypable + ::std::clone::Clone + ::std::cmp::PartialEq
+                    + ::std::convert::Into<::qml::QVariant>
+                    + for<'a> ::std::convert::TryFrom<&'a ::qml::QVariant, Error = Conversion>
+            {
+            }
+
+            fn check() {
+                #(#assertions)*
+            }
+        };
+
+        #input
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse::{Parse, ParseStream};
+    use syn::{braced, parse_quote, Block, Expr, GenericArgument, ImplItemFn, Item, PathArguments, Stmt};
+
+    /// A property or a member, as it's declared in `Q_OBJECT!`.
+    #[derive(Debug, PartialEq)]
+    struct Declared {
+        name: Ident,
+        ty: Type,
+        accessors: Vec<(Ident, Ident)>,
+    }
+
+    impl Declared {
+        fn new(name: &str, ty: Type, accessors: &[(&str, &str)]) -> Self {
+            let ident = |name: &str| Ident::new(name, proc_macro2::Span::call_site());
+            Declared {
+                name: ident(name),
+                ty,
+                accessors: accessors.iter().map(|&(kind, name)| (ident(kind), ident(name))).collect(),
+            }
+        }
+    }
+
+    impl Parse for Declared {
+        fn parse(input: ParseStream) -> Result<Self> {
+            let name = input.parse()?;
+            input.parse::<Token![:]>()?;
+            let ty = input.parse()?;
+            input.parse::<Token![;]>()?;
+            let mut accessors = Vec::new();
+            while !input.peek(Token![;]) {
+                if !accessors.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                let kind = input.parse()?;
+                input.parse::<Token![:]>()?;
+                accessors.push((kind, input.parse()?));
+            }
+            input.parse::<Token![;]>()?;
+            Ok(Declared { name, ty, accessors })
+        }
+    }
+
+    /// Sections of a `Q_OBJECT!` invocation.
+    #[derive(Debug)]
+    struct QObjectDecl {
+        origin: Ident,
+        wrapper: Ident,
+        signals: Vec<TraitItemFn>,
+        slots: Vec<TraitItemFn>,
+        members: Vec<Declared>,
+        properties: Vec<Declared>,
+    }
+
+    impl Parse for QObjectDecl {
+        fn parse(input: ParseStream) -> Result<Self> {
+            input.parse::<Token![pub]>()?;
+            let origin = input.parse()?;
+            input.parse::<Token![as]>()?;
+            let wrapper = input.parse()?;
+            let content;
+            braced!(content in input);
+            let mut decl = QObjectDecl {
+                origin,
+                wrapper,
+                signals: Vec::new(),
+                slots: Vec::new(),
+                members: Vec::new(),
+                properties: Vec::new(),
+            };
+            let sections = ["signals", "slots", "members", "properties"];
+            for (i, section) in sections.iter().enumerate() {
+                let ident: Ident = content.parse()?;
+                assert_eq!(ident, section);
+                content.parse::<Token![:]>()?;
+                let next_section = |content: ParseStream| {
+                    sections.get(i + 1).map_or(content.is_empty(), |next| {
+                        content.peek2(Token![:]) && content.fork().parse::<Ident>().is_ok_and(|ident| ident == next)
+                    })
+                };
+                while !next_section(&content) {
+                    match *section {
+                        "signals" => decl.signals.push(content.parse()?),
+                        "slots" => decl.slots.push(content.parse()?),
+                        "members" => {
+                            content.parse::<Token![self]>()?;
+                            content.parse::<Token![.]>()?;
+                            decl.members.push(content.parse()?);
+                        }
+                        _ => decl.properties.push(content.parse()?),
+                    }
+                }
+            }
+            Ok(decl)
+        }
+    }
+
+    /// The expansion of `#[qobject]`: the declaration, the type checks and the impl block.
+    struct Expansion {
+        decl: QObjectDecl,
+        checks: Vec<(String, Type)>,
+        items: Vec<ImplItemFn>,
+    }
+
+    fn checks_of(block: &Block) -> Vec<(String, Type)> {
+        let check = block.stmts
+            .iter()
+            .filter_map(|stmt| match *stmt {
+                Stmt::Item(Item::Fn(ref f)) if f.sig.ident == "check" => Some(f),
+                _ => None,
+            })
+            .next()
+            .expect("no `check` function");
+        check.block
+            .stmts
+            .iter()
+            .map(|stmt| {
+                let call = match *stmt {
+                    Stmt::Expr(Expr::Call(ref call), Some(_)) => call,
+                    _ => panic!("expected a call, found {:?}", stmt),
+                };
+                let segment = match *call.func {
+                    Expr::Path(ref path) => path.path.segments.last().unwrap().clone(),
+                    _ => panic!("expected a function"),
+                };
+                let ty = match segment.arguments {
+                    PathArguments::AngleBracketed(ref args) => {
+                        match args.args[0] {
+                            GenericArgument::Type(ref ty) => ty.clone(),
+                            _ => panic!("expected a type"),
+                        }
+                    }
+                    _ => panic!("expected a checked type"),
+                };
+                (segment.ident.to_string(), ty)
+            })
+            .collect()
+    }
+
+    fn expand(input: ItemImpl) -> Result<Expansion> {
+        let origin = Ident::new("Counter", proc_macro2::Span::call_site());
+        let file: syn::File = syn::parse2(expand_qobject(origin, input)?)?;
+        let mut decl = None;
+        let mut checks = None;
+        let mut items = None;
+        for item in file.items {
+            match item {
+                Item::Macro(ref m) if m.mac.path.is_ident("Q_OBJECT") => decl = Some(m.mac.parse_body()?),
+                Item::Const(ref c) => {
+                    match *c.expr {
+                        Expr::Block(ref block) => checks = Some(checks_of(&block.block)),
+                        _ => panic!("expected a block of type checks"),
+                    }
+                }
+                Item::Impl(i) => {
+                    items = Some(i.items
+                        .into_iter()
+                        .map(|item| match item {
+                            ImplItem::Fn(f) => f,
+                            _ => panic!("expected only methods"),
+                        })
+                        .collect())
+                }
+                item => panic!("unexpected item {:?}", item),
+            }
+        }
+        Ok(Expansion {
+            decl: decl.expect("no Q_OBJECT!"),
+            checks: checks.expect("no type checks"),
+            items: items.expect("no impl block"),
+        })
+    }
+
+    #[test]
+    fn items_are_lowered_to_q_object() {
+        let expansion = expand(parse_quote! {
+            impl QCounter {
+                #[qsignal]
+                fn overflow(&self, at: i32);
+
+                #[qslot]
+                fn describe(&self, prefix: String) -> String {
+                    prefix
+                }
+
+                #[qproperty(notify = title_updated)]
+                fn title(&self) -> String;
+
+                #[qmember(write, notify)]
+                fn count(&self) -> i32;
+
+                #[qmember(read = step)]
+                fn step(&self) -> i32;
+
+                fn helper(&self) {}
+            }
+        }).unwrap();
+        let decl = expansion.decl;
+        assert_eq!(decl.origin, "Counter");
+        assert_eq!(decl.wrapper, "QCounter");
+        assert_eq!(decl.signals, vec![parse_quote!(fn overflow(at: i32);)]);
+        assert_eq!(decl.slots, vec![parse_quote!(fn describe(prefix: String) -> String;)]);
+        assert_eq!(decl.members,
+                   vec![Declared::new("count",
+                                      parse_quote!(i32),
+                                      &[("read", "get_count"), ("write", "set_count"), ("notify", "count_changed")]),
+                        Declared::new("step", parse_quote!(i32), &[("read", "step")])]);
+        assert_eq!(decl.properties,
+                   vec![Declared::new("title",
+                                      parse_quote!(String),
+                                      &[("read", "get_title"), ("write", "set_title"), ("notify", "title_updated")])]);
+        // Slots and other methods stay in the impl block, while signals and properties are generated
+        let describe: ImplItemFn = parse_quote!(fn describe(&self, prefix: String) -> String { prefix });
+        let helper: ImplItemFn = parse_quote!(fn helper(&self) {});
+        assert_eq!(expansion.items, vec![describe, helper]);
+    }
+
+    #[test]
+    fn types_are_checked_where_declared() {
+        let expansion = expand(parse_quote! {
+            impl QCounter {
+                #[qsignal]
+                fn overflow(&self, at: i32);
+
+                #[qslot]
+                fn describe(&self, prefix: String) -> Result<String, String> {
+                    Ok(prefix)
+                }
+
+                #[qproperty]
+                fn title(&self) -> String;
+
+                #[qmember(write)]
+                fn count(&self) -> i32;
+
+                #[qmember]
+                fn step(&self) -> u32;
+            }
+        }).unwrap();
+        let expected: Vec<(&str, Type)> = vec![("signal_argument", parse_quote!(i32)),
+                                               ("slot_argument", parse_quote!(String)),
+                                               ("slot_return", parse_quote!(Result<String, String>)),
+                                               ("property", parse_quote!(String)),
+                                               ("writable_member", parse_quote!(i32)),
+                                               ("member", parse_quote!(u32))];
+        let expected: Vec<(String, Type)> = expected.into_iter().map(|(check, ty)| (check.to_string(), ty)).collect();
+        assert_eq!(expansion.checks, expected);
+    }
+
+    #[test]
+    fn malformed_items_are_rejected() {
+        let error = |input: ItemImpl| expand(input).err().unwrap().to_string();
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qsignal] fn clicked(&self) -> i32; })),
+                   "signals can't return a value");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qsignal] fn clicked(&self) {} })),
+                   "signals, properties and members are generated, remove the body");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qslot] fn click(&self); })),
+                   "a slot should have a body");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qslot] fn click(self) {} })),
+                   "expected a method taking `&self` or `&mut self`");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qslot] fn click<T>(&self, t: T) {} })),
+                   "slots and signals can't be generic");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qslot] #[qsignal] fn click(&self) {} })),
+                   "an item can be only one of a slot, a signal, a property or a member");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { fn click(&self); })),
+                   "expected `#[qsignal]`, `#[qproperty]` or `#[qmember]`");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qproperty] fn name(&self); })),
+                   "expected a property like `fn name(&self) -> Type;`");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qmember] fn count(&self, x: i32) -> i32; })),
+                   "expected a member like `fn field(&self) -> Type;`");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qproperty(reset)] fn name(&self) -> String; })),
+                   "unknown qproperty attribute, expected `read`, `write` or `notify`");
+        assert_eq!(error(syn::parse_quote!(impl QCounter { #[qmember(read)] fn count(&self) -> i32; })),
+                   "expected a name of the read slot: `read = name`");
+        assert_eq!(error(syn::parse_quote!(impl Vec<QCounter> {})), "expected a name of a wrapper to generate");
+        assert_eq!(error(syn::parse_quote!(impl Clone for QCounter {})), "expected an inherent impl block");
+    }
+}
@@ -0,0 +1,409 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{Attribute, Data, DeriveInput, Fields, GenericParam, Generics, Ident, Index, Lifetime, LifetimeParam,
+          LitStr, Result, Type, WherePredicate};
+use syn::spanned::Spanned;
+
+/// How a type is represented in a `QVariant`.
+enum Shape<'a> {
+    /// A `QVariantMap` of fields and their keys
+    Map(Vec<(&'a Ident, String)>),
+    /// A `QVariantList` with the given number of elements
+    List(usize),
+    /// An `int` or a `QString` of variants and their names
+    Enum {
+        string: bool,
+        variants: Vec<(&'a Ident, String)>,
+    },
+}
+
+#[derive(Default)]
+struct QmlAttrs {
+    rename: Option<String>,
+    string: bool,
+}
+
+fn parse_attrs(attrs: &[Attribute]) -> Result<QmlAttrs> {
+    let mut res = QmlAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("qml")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                res.rename = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("string") {
+                res.string = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown qml attribute, expected `rename` or `string`"))
+            }
+        })?;
+    }
+    Ok(res)
+}
+
+fn shape(input: &DeriveInput) -> Result<Shape<'_>> {
+    let attrs = parse_attrs(&input.attrs)?;
+    if attrs.rename.is_some() {
+        return Err(syn::Error::new_spanned(&input.ident, "`rename` is allowed only on fields and variants"));
+    }
+    match input.data {
+        Data::Struct(ref data) => {
+            if attrs.string {
+                return Err(syn::Error::new_spanned(&input.ident, "`string` is allowed only on enums"));
+            }
+            match data.fields {
+                Fields::Named(ref fields) => {
+                    let mut keys = Vec::new();
+                    for field in &fields.named {
+                        let ident = field.ident.as_ref().unwrap();
+                        let key = parse_attrs(&field.attrs)?.rename.unwrap_or_else(|| ident.to_string());
+                        keys.push((ident, key));
+                    }
+                    Ok(Shape::Map(keys))
+                }
+                Fields::Unnamed(ref fields) => {
+                    for field in &fields.unnamed {
+                        if parse_attrs(&field.attrs)?.rename.is_some() {
+                            return Err(syn::Error::new_spanned(field, "fields of tuple structs can't be renamed"));
+                        }
+                    }
+                    Ok(Shape::List(fields.unnamed.len()))
+                }
+                Fields::Unit => {
+                    Err(syn::Error::new_spanned(&input.ident, "unit structs can't be converted into a QVariant"))
+                }
+            }
+        }
+        Data::Enum(ref data) => {
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                match variant.fields {
+                    Fields::Unit => {}
+                    _ => return Err(syn::Error::new_spanned(variant, "only enums without fields are supported")),
+                }
+                let name = parse_attrs(&variant.attrs)?.rename.unwrap_or_else(|| variant.ident.to_string());
+                variants.push((&variant.ident, name));
+            }
+            Ok(Shape::Enum {
+                string: attrs.string,
+                variants,
+            })
+        }
+        Data::Union(_) => Err(syn::Error::new_spanned(&input.ident, "unions can't be converted into a QVariant")),
+    }
+}
+
+/// Types of all fields of a struct, enums have none.
+fn field_types(input: &DeriveInput) -> Vec<&Type> {
+    match input.data {
+        Data::Struct(ref data) => data.fields.iter().map(|field| &field.ty).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Adds a bound for every field to the generics of a generic type, so that errors point at the field.
+///
+/// Fields of non-generic types need no bounds, as their conversions are checked in the generated code itself.
+fn bound_fields<F>(input: &DeriveInput, bound: F) -> Generics
+    where F: Fn(&Type) -> Vec<WherePredicate>
+{
+    let mut generics = input.generics.clone();
+    if generics.type_params().next().is_some() {
+        let predicates = field_types(input).into_iter().flat_map(bound).collect::<Vec<_>>();
+        generics.make_where_clause().predicates.extend(predicates);
+    }
+    generics
+}
+
+pub fn expand_to_qvariant(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let generics = bound_fields(input, |ty| {
+        vec![syn::parse_quote_spanned!(ty.span()=> #ty: ::std::convert::Into<::qml::QVariant>)]
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (body, metatype) = match shape(input)? {
+        Shape::Map(fields) => {
+            let inserts = fields.iter().map(|&(ident, ref key)| {
+                quote! {
+                    map.insert(#key.to_string(), i.#ident.into());
+                }
+            });
+            let body = quote! {
+                let mut map: ::std::collections::HashMap<String, ::qml::QVariant> =
+                    ::std::collections::HashMap::new();
+                #(#inserts)*
+                map.into()
+            };
+            (body, quote!(QVariantMap))
+        }
+        Shape::List(len) => {
+            let indices = (0..len).map(Index::from);
+            let body = quote! {
+                let list: Vec<::qml::QVariant> = vec![#(i.#indices.into()),*];
+                list.into()
+            };
+            (body, quote!(QVariantList))
+        }
+        Shape::Enum { string: true, variants } => {
+            let arms = variants.iter().map(|&(ident, ref key)| quote!(#name::#ident => #key));
+            let body = quote! {
+                ::qml::QVariant::from(match i {
+                    #(#arms,)*
+                })
+            };
+            (body, quote!(QString))
+        }
+        Shape::Enum { string: false, variants } => {
+            let arms = variants.iter().map(|&(ident, _)| quote!(#name::#ident => #name::#ident as i32));
+            let body = quote! {
+                ::qml::QVariant::from(match i {
+                    #(#arms,)*
+                })
+            };
+            (body, quote!(Int))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<#name #ty_generics> for ::qml::QVariant #where_clause {
+            fn from(i: #name #ty_generics) -> Self {
+                #body
+            }
+        }
+
+        impl #impl_generics ::qml::QMetaTypable for #name #ty_generics #where_clause {
+            fn metatype() -> ::qml::QMetaType {
+                ::qml::QMetaType::#metatype
+            }
+        }
+    })
+}
+
+pub fn expand_from_qvariant(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let bounded = bound_fields(input, |ty| {
+        vec![syn::parse_quote_spanned!(ty.span()=> #ty: for<'__qml_field> ::std::convert::TryFrom<&'__qml_field ::qml::QVariant>),
+             syn::parse_quote_spanned! {ty.span()=>
+                 for<'__qml_field> ::qml::QVariantConversionError:
+                     ::std::convert::From<<#ty as ::std::convert::TryFrom<&'__qml_field ::qml::QVariant>>::Error>
+             }]
+    });
+    let (owned_impl_generics, ty_generics, where_clause) = bounded.split_for_impl();
+    let mut generics = bounded.clone();
+    let lifetime = Lifetime::new("'__qml", Span::call_site());
+    generics.params.insert(0, GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())));
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    let body = match shape(input)? {
+        Shape::Map(fields) => {
+            let fields = fields.iter().map(|&(ident, ref key)| {
+                quote! {
+                    #ident: match map.remove(#key) {
+                        Some(value) => ::std::convert::TryFrom::try_from(&value)?,
+                        // A missing key is `undefined` in QML, which is fine for an `Option`
+                        None => ::std::convert::TryFrom::try_from(&::qml::QVariant::default()).map_err(|_| {
+                            ::qml::QVariantConversionError::MissingKey {
+                                expected: #type_name,
+                                key: #key.to_string(),
+                            }
+                        })?,
+                    }
+                }
+            });
+            quote! {
+                let mut map = match i.to_map() {
+                    Some(map) => map,
+                    None => return Err(::qml::QVariantConversionError::TypeMismatch {
+                        expected: "QVariantMap",
+                        actual: i.type_name(),
+                    }),
+                };
+                Ok(#name { #(#fields),* })
+            }
+        }
+        Shape::List(len) => {
+            let elements = (0..len).map(|_| {
+                quote! {
+                    ::std::convert::TryFrom::try_from(&iter.next().unwrap())?
+                }
+            });
+            quote! {
+                let list = match i.to_list() {
+                    Some(list) => list,
+                    None => return Err(::qml::QVariantConversionError::TypeMismatch {
+                        expected: "QVariantList",
+                        actual: i.type_name(),
+                    }),
+                };
+                if list.len() != #len {
+                    return Err(::qml::QVariantConversionError::OutOfRange {
+                        expected: #type_name,
+                        value: format!("QVariantList of {} elements", list.len()),
+                    });
+                }
+                let mut iter = list.into_iter();
+                Ok(#name(#(#elements),*))
+            }
+        }
+        Shape::Enum { string: true, variants } => {
+            let arms = variants.iter().map(|&(ident, ref key)| quote!(#key => Ok(#name::#ident)));
+            quote! {
+                let value = <String as ::std::convert::TryFrom<&::qml::QVariant>>::try_from(i)?;
+                match value.as_str() {
+                    #(#arms,)*
+                    _ => Err(::qml::QVariantConversionError::OutOfRange {
+                        expected: #type_name,
+                        value: format!("{:?}", value),
+                    }),
+                }
+            }
+        }
+        Shape::Enum { string: false, variants } => {
+            let arms = variants.iter()
+                .map(|&(ident, _)| quote!(value if value == #name::#ident as i32 => Ok(#name::#ident)));
+            quote! {
+                let value = <i32 as ::std::convert::TryFrom<&::qml::QVariant>>::try_from(i)?;
+                match value {
+                    #(#arms,)*
+                    _ => Err(::qml::QVariantConversionError::OutOfRange {
+                        expected: #type_name,
+                        value: value.to_string(),
+                    }),
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::TryFrom<&#lifetime ::qml::QVariant> for #name #ty_generics #where_clause {
+            type Error = ::qml::QVariantConversionError;
+
+            fn try_from(i: &#lifetime ::qml::QVariant) -> Result<Self, Self::Error> {
+                #body
+            }
+        }
+
+        impl #owned_impl_generics ::std::convert::TryFrom<::qml::QVariant> for #name #ty_generics #where_clause {
+            type Error = ::qml::QVariantConversionError;
+
+            fn try_from(i: ::qml::QVariant) -> Result<Self, Self::Error> {
+                <Self as ::std::convert::TryFrom<&::qml::QVariant>>::try_from(&i)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Expanded code without spaces, which `quote!` puts between all tokens.
+    fn expanded(code: Result<TokenStream>) -> String {
+        code.unwrap().to_string().replace(' ', "")
+    }
+
+    fn error(code: Result<TokenStream>) -> String {
+        code.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn structs_become_maps() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Person {
+                name: String,
+                #[qml(rename = "years")]
+                age: u32,
+            }
+        };
+        let to = expanded(expand_to_qvariant(&input));
+        assert!(to.contains(r#"map.insert("name".to_string(),i.name.into());"#));
+        assert!(to.contains(r#"map.insert("years".to_string(),i.age.into());"#));
+        assert!(to.contains("::qml::QMetaType::QVariantMap"));
+
+        let from = expanded(expand_from_qvariant(&input));
+        assert!(from.contains(r#"age:matchmap.remove("years")"#));
+        assert!(from.contains(r#"::qml::QVariantConversionError::MissingKey{expected:"Person",key:"years".to_string(),}"#));
+        assert!(from.contains(r#"expected:"QVariantMap""#));
+    }
+
+    #[test]
+    fn tuple_structs_become_lists_of_fixed_length() {
+        let input: DeriveInput = syn::parse_quote!(struct Point(f64, f64););
+        let to = expanded(expand_to_qvariant(&input));
+        assert!(to.contains("vec![i.0.into(),i.1.into()]"));
+        assert!(to.contains("::qml::QMetaType::QVariantList"));
+
+        let from = expanded(expand_from_qvariant(&input));
+        assert!(from.contains("iflist.len()!=2usize"));
+        assert!(from.contains(r#"::qml::QVariantConversionError::OutOfRange{expected:"Point",value:format!("QVariantListof{}elements",list.len()),}"#));
+    }
+
+    #[test]
+    fn enums_become_ints() {
+        let input: DeriveInput = syn::parse_quote!(enum Level { Low = 1, High = 10 });
+        let to = expanded(expand_to_qvariant(&input));
+        assert!(to.contains("Level::High=>Level::Highasi32"));
+        assert!(to.contains("::qml::QMetaType::Int"));
+
+        let from = expanded(expand_from_qvariant(&input));
+        assert!(from.contains("valueifvalue==Level::Lowasi32=>Ok(Level::Low)"));
+        assert!(from.contains(r#"OutOfRange{expected:"Level",value:value.to_string(),}"#));
+    }
+
+    #[test]
+    fn string_enums_become_names() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[qml(string)]
+            enum Mode {
+                Dark,
+                #[qml(rename = "light")]
+                Light,
+            }
+        };
+        let to = expanded(expand_to_qvariant(&input));
+        assert!(to.contains(r#"Mode::Dark=>"Dark",Mode::Light=>"light""#));
+        assert!(to.contains("::qml::QMetaType::QString"));
+
+        let from = expanded(expand_from_qvariant(&input));
+        assert!(from.contains(r#""light"=>Ok(Mode::Light)"#));
+        assert!(from.contains(r#"OutOfRange{expected:"Mode",value:format!("{:?}",value),}"#));
+    }
+
+    #[test]
+    fn fields_of_generic_types_are_bound() {
+        let input: DeriveInput = syn::parse_quote!(struct Pair<A> { first: A, second: i32 });
+        let to = expanded(expand_to_qvariant(&input));
+        assert!(to.contains("whereA:::std::convert::Into<::qml::QVariant>,i32:::std::convert::Into<::qml::QVariant>"));
+
+        let from = expanded(expand_from_qvariant(&input));
+        assert!(from.contains("A:for<'__qml_field>::std::convert::TryFrom<&'__qml_field::qml::QVariant>"));
+        assert!(from.contains("::qml::QVariantConversionError:::std::convert::From<<Aas::std::convert::TryFrom"));
+
+        let input: DeriveInput = syn::parse_quote!(struct Plain { first: String });
+        assert!(!expanded(expand_to_qvariant(&input)).contains("where"));
+    }
+
+    #[test]
+    fn unsupported_types_are_rejected() {
+        let unit: DeriveInput = syn::parse_quote!(struct Unit;);
+        assert_eq!(error(expand_to_qvariant(&unit)), "unit structs can't be converted into a QVariant");
+        let union: DeriveInput = syn::parse_quote!(union Bits { a: u32, b: f32 });
+        assert_eq!(error(expand_from_qvariant(&union)), "unions can't be converted into a QVariant");
+        let fields: DeriveInput = syn::parse_quote!(enum Shape { Circle(f64) });
+        assert_eq!(error(expand_to_qvariant(&fields)), "only enums without fields are supported");
+    }
+
+    #[test]
+    fn misplaced_attributes_are_rejected() {
+        let renamed: DeriveInput = syn::parse_quote!(#[qml(rename = "p")] struct Point { x: f64 });
+        assert_eq!(error(expand_to_qvariant(&renamed)), "`rename` is allowed only on fields and variants");
+        let string: DeriveInput = syn::parse_quote!(#[qml(string)] struct Point { x: f64 });
+        assert_eq!(error(expand_to_qvariant(&string)), "`string` is allowed only on enums");
+        let tuple: DeriveInput = syn::parse_quote!(struct Point(#[qml(rename = "x")] f64););
+        assert_eq!(error(expand_from_qvariant(&tuple)), "fields of tuple structs can't be renamed");
+        let unknown: DeriveInput = syn::parse_quote!(struct Point { #[qml(skip)] x: f64 });
+        assert_eq!(error(expand_to_qvariant(&unknown)), "unknown qml attribute, expected `rename` or `string`");
+    }
+}
@@ -4,6 +4,15 @@
 extern crate libc;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "derive")]
+extern crate qml_macros;
 
 mod qmlengine;
 mod qvariant;
@@ -16,18 +25,40 @@ mod qurl;
 mod qobject;
 mod qmeta;
 mod qtypes;
+mod qdatetime;
+mod qgeometry;
+mod qcolor;
+mod qbytearray;
+mod qpanic;
+mod qconnection;
+mod qhandle;
 #[macro_use]
 mod macros;
 mod qmlregister;
+#[cfg(feature = "serde")]
+mod qserde;
+#[cfg(feature = "serde_json")]
+mod qjson;
 
 pub use qmlengine::QmlEngine;
-pub use qvariant::QVariant;
+pub use qpanic::{PanicPolicy, CallbackPanic};
+pub use qvariant::{QVariant, QVariantRef, QVariantValue, QVariantConversionError};
+pub use qdatetime::{QDate, QTime, QDateTime};
+pub use qgeometry::{QPointF, QSizeF, QRectF};
+pub use qcolor::QColor;
+pub use qbytearray::QByteArray;
 pub use qabstractlistmodel::{QModel, QAbstractListModel, QListModel};
 pub use qmodelindex::QModelIndex;
-pub use qobject::QObject;
-pub use qmeta::{QObjectMacro, emit_signal};
+pub use qobject::{QObject, QObjectRef, QtConnectionType, QtConnectionFlags};
+pub use qconnection::{Connection, ConnectionError, SignalHandler, connect};
+pub use qhandle::QObjectHandle;
+pub use qmeta::{QObjectMacro, QSlotReturn, emit_signal};
 pub use qtypes::*;
 pub use qmlregister::QMLRegisterable;
+#[cfg(feature = "serde")]
+pub use qserde::{to_qvariant, from_qvariant, SerdeError};
+#[cfg(feature = "derive")]
+pub use qml_macros::{ToQVariant, FromQVariant, qobject};
 
 #[doc(hidden)]
 pub use libc::c_void;
@@ -32,10 +32,38 @@ macro_rules! qvarlist{
     }};
 }
 
+/// Eases forming of `QVariantMaps` ([`QVariant`](struct.QVariant.html) of a map, an object in QML).
+///
+/// Macro generates HashMap<String, QVariant> which implements Into<QVariant>.
+/// Values can be anything convertible into `QVariant`, including `qvarlist!` and other `qvarmap!`.
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate qml;
+/// # use qml::*;
+/// # fn main() {
+/// let person: QVariant = qvarmap!{
+///     "name" => "John",
+///     "id" => 3,
+///     "scores" => qvarlist![2, 2],
+///     "address" => qvarmap!{ "city" => "Moscow" },
+/// }.into();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! qvarmap{
+    ($($key:expr => $value:expr),* $(,)*) => {{
+        let mut m: ::std::collections::HashMap<String, QVariant> = ::std::collections::HashMap::new();
+        $(
+            m.insert($key.into(), $value.into());
+        )*
+        m
+    }};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __gen_signals{
-    (fn $signalname:ident ( $( $signalvar:ident : $signalqtype:ident ),* ); $($rest:tt)*) =>{
+    (fn $signalname:ident ( $( $signalvar:ident : $signalqtype:ty ),* ); $($rest:tt)*) =>{
         pub fn $signalname(&self, $( $signalvar: $signalqtype ),*){
             let mut vec: Vec<QVariant> = Vec::new();
             $(
@@ -50,8 +78,76 @@ macro_rules! __gen_signals{
     () => ();
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_call{
+    ($call:expr) => {{
+        $call;
+        Ok(None)
+    }};
+    ($call:expr, $ret:ty) => {{
+        let ret: $ret = $call;
+        QSlotReturn::into_slot_result(ret)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __slot_return_metatype{
+    () => (QMetaType::Void as i32);
+    ($ret:ty) => (<$ret as QMetaTypable>::metatype() as i32);
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __gen_member_write{
+    ($field:ident : $ty:ty; ; $($notify:ident)*) => ();
+    ($field:ident : $ty:ty; $write:ident; $($notify:ident)*) => {
+        pub fn $write(&mut self, input: $ty) {
+            if self.origin.$field != input {
+                self.origin.$field = input;
+                $(self.$notify();)*
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __member_name{
+    () => ("");
+    ($name:ident) => (stringify!($name));
+}
+
 /// Marks the structure to be able to be used in Qt meta-object system.
 ///
+/// Arguments of slots are converted from `QVariant` with `TryFrom<&QVariant>`,
+/// so types of slot parameters should implement it. If QML passes a value
+/// of a wrong type, the call throws a JavaScript `Error` with the description
+/// of [`QVariantConversionError`](enum.QVariantConversionError.html).
+/// Parameters and properties, that can be `null` or `undefined` in QML, should be declared as `Option<T>`.
+///
+/// A slot may declare a return type, like `fn compute(x: i32) -> String;`, which should implement
+/// `Into<QVariant>` and [`QMetaTypable`](trait.QMetaTypable.html). The value is passed back to QML.
+/// Slots without a return type return `()`.
+/// A slot returning `Result<T, E>` with `E: Display` throws a JavaScript `Error` on `Err`,
+/// see [`QSlotReturn`](trait.QSlotReturn.html).
+///
+/// Properties are read and written with the generated `read` and `write` methods. A write, either from QML or from Rust,
+/// emits the `notify` signal if the value has changed, so bindings in QML are updated.
+///
+/// Properties can also be backed by fields of the wrapped structure, declared in an optional `members:` section
+/// before `properties:`. Such a property is named after its field, and its type should implement `Clone` and `PartialEq`.
+/// `write` and `notify` are optional for members:
+///
+/// * `self.count: i32; read: get_count, write: set_count, notify: count_changed;` is a usual property;
+/// * `self.total: i32; read: get_total, notify: total_changed;` is read-only in QML. Changing it from Rust,
+///   call the `notify` signal yourself;
+/// * `self.id: i32; read: get_id;` is a constant, marked as `CONSTANT` in the meta-object system,
+///   so QML bindings to it don't expect it to change.
+///
+/// Rust code accesses members as plain fields, e.g. `self.count`, but only the `write` method emits the `notify` signal.
+///
 /// # Examples
 ///
 /// ```
@@ -60,9 +156,16 @@ macro_rules! __gen_signals{
 /// pub struct Example;
 ///
 /// impl Example {
-///     pub fn simple_receiver(&mut self) -> Option<&QVariant> {
+///     pub fn simple_receiver(&mut self) {
 ///         // This is a function that also will be a slot
-///         None
+///     }
+///
+///     pub fn compute(&self, x: i32) -> String {
+///         format!("{} from Rust", x)
+///     }
+///
+///     pub fn parse(&self, s: String) -> Result<i32, std::num::ParseIntError> {
+///         s.parse()
 ///     }
 /// }
 ///
@@ -72,6 +175,8 @@ macro_rules! __gen_signals{
 ///         fn simple_signal(s: String);
 ///     slots:
 ///         fn simple_receiver();
+///         fn compute(x: i32) -> String;
+///         fn parse(s: String) -> Result<i32, std::num::ParseIntError>;
 ///     properties:
 ///         name: String; read: get_name, write: set_name, notify: name_changed;
 /// });
@@ -84,18 +189,52 @@ macro_rules! __gen_signals{
 /// qobject.simple_signal("Hi from Rust!".into());
 /// # }
 /// ```
+///
+/// With members:
+///
+/// ```
+/// # #[macro_use] extern crate qml;
+/// # use qml::*;
+/// pub struct Counter {
+///     count: i32,
+///     step: i32,
+/// }
+///
+/// impl QCounter {
+///     pub fn increment(&mut self) {
+///         let count = self.count + self.step;
+///         self.set_count(count);
+///     }
+/// }
+///
+/// Q_OBJECT!(
+/// pub Counter as QCounter{
+///     signals:
+///     slots:
+///         fn increment();
+///     members:
+///         self.count: i32; read: get_count, write: set_count, notify: count_changed;
+///         self.step: i32; read: get_step;
+///     properties:
+/// });
+/// # fn main() {}
+/// ```
 #[macro_export]
 macro_rules! Q_OBJECT{
     (
         pub $obj:ident as $wrapper:ident{
             signals:
-            $(fn $signalname:ident ( $( $signalvar:ident : $signalqtype:ident ),* );)*
+            $(fn $signalname:ident ( $( $signalvar:ident : $signalqtype:ty ),* );)*
 
             slots:
-            $(fn $slotname:ident ( $( $slotvar:ident : $slotqtype:ident ),* );)*
+            $(fn $slotname:ident ( $( $slotvar:ident : $slotqtype:ty ),* ) $(-> $slotret:ty)*;)*
+
+            members:
+            $(self.$member:ident : $membertype:ty; read: $member_read:ident $(, write: $member_write:ident)*
+                $(, notify: $member_notify:ident)*;)*
 
             properties:
-            $($propname:ident : $proptype:ident; read: $read_slot:ident, write: $write_slot:ident,
+            $($propname:ident : $proptype:ty; read: $read_slot:ident, write: $write_slot:ident,
                 notify: $notify_sig:ident;)*
             }) =>{
                 pub struct $wrapper{
@@ -121,7 +260,7 @@ macro_rules! Q_OBJECT{
 
                 impl $wrapper{
                     __gen_signals!($(fn $signalname ( $( $signalvar : $signalqtype ),* );)*
-                    $(fn $notify_sig ();)*);
+                    $(fn $notify_sig ();)* $($(fn $member_notify ();)*)*);
 
                     pub fn with_no_props(origin: $obj)-> Box<Self> {
                         unsafe{
@@ -130,7 +269,7 @@ macro_rules! Q_OBJECT{
                                 ptr: ::std::mem::uninitialized(),
                                 properties: ::std::collections::HashMap::new(),
                             };
-                            $(local.properties.insert(stringify!($propname), ($proptype::default().into(), $proptype::metatype()));)*
+                            $(local.properties.insert(stringify!($propname), (<$proptype>::default().into(), <$proptype as QMetaTypable>::metatype()));)*
                             let mut local = Box::new(local);
                             let qobj = QObject::new(&mut *local);
                             ::std::ptr::write(&mut local.ptr, qobj);
@@ -140,56 +279,74 @@ macro_rules! Q_OBJECT{
 
                     pub fn new(origin: $obj, $($propname: $proptype),*) -> Box<Self>{
                         let mut local = Self::with_no_props(origin);
-                        $(local.properties.insert(stringify!($propname), ($propname.into(), $proptype::metatype()));)*
+                        $(local.properties.insert(stringify!($propname), ($propname.into(), <$proptype as QMetaTypable>::metatype()));)*
                         local
                     }
 
                     $(pub fn $read_slot(&self) -> &QVariant {
-                        println!("Trying to read");
                         &self.properties.get(stringify!($propname)).unwrap().0
                     }
 
                     pub fn $write_slot(&mut self, input: $proptype) {
-                        self.properties.insert(stringify!($propname), (input.into(), $proptype::metatype()));
+                        let input: QVariant = input.into();
+                        let changed = match self.properties.get(stringify!($propname)) {
+                            Some(&(ref old, _)) => *old != input,
+                            None => true,
+                        };
+                        self.properties.insert(stringify!($propname), (input, <$proptype as QMetaTypable>::metatype()));
+                        if changed {
+                            self.$notify_sig();
+                        }
                     })*
 
-                    fn threaded<F: FnOnce(&mut $wrapper) + Send + 'static>(&mut self, f: F){
-                        let ptr = ::std::sync::atomic::AtomicPtr::new(self);
-                        ::std::thread::spawn(move || {
-                            f(unsafe { &mut *ptr.load(::std::sync::atomic::Ordering::Relaxed) });
-                        });
+                    $(pub fn $member_read(&self) -> $membertype {
+                        self.origin.$member.clone()
+                    }
+
+                    __gen_member_write!($member: $membertype; $($member_write)*; $($member_notify)*);)*
+
+                    /// Returns a handle, that can be sent to other threads to queue work on this object.
+                    pub fn handle(&self) -> QObjectHandle<$wrapper> {
+                        QObjectHandle::new(self)
                     }
                 }
 
                 impl QObjectMacro for $wrapper{
-                    fn qslot_call(&mut self, name: &str, args: Vec<QVariant>) -> Option<&QVariant>{
-                        fn next_or_panic(qt: Option<QVariant>) -> QVariant{
-                            if let Some(o) = qt {
-                                o
-                            }else {
-                                panic!("Not enough parameters to call a slot")
+                    fn qslot_call(&mut self, name: &str, args: Vec<QVariantRef>) -> Result<Option<QVariant>, String>{
+                        fn convert<T>(qt: Option<&QVariantRef>, slot: &str, arg: &str) -> Result<T, String>
+                            where T: for<'a> ::std::convert::TryFrom<&'a QVariant, Error = QVariantConversionError>
+                        {
+                            match qt {
+                                Some(qt) => ::std::convert::TryFrom::try_from(&**qt)
+                                    .map_err(|e| format!("Wrong parameter `{}` for a slot `{}`: {}", arg, slot, e)),
+                                None => Err(format!("Not enough parameters to call a slot `{}`", slot)),
                             }
                         }
                         match name {
                             $(stringify!($slotname) => {
-                                let mut iter = args.into_iter();
+                                let mut iter = args.iter();
                                 $(
-                                    let next = next_or_panic (iter.next());
-                                    let $slotvar: $slotqtype = next.into();
+                                    let $slotvar: $slotqtype = convert(iter.next(), name, stringify!($slotvar))?;
                                 )*
-                                self.$slotname ($($slotvar),*)
+                                __slot_call!(self.$slotname ($($slotvar),*) $(, $slotret)*)
                             },)*
+                            $(stringify!($member_read) => {
+                                Ok(Some(self.$member_read ().into()))
+                            },
+                            $(stringify!($member_write) => {
+                                let member: $membertype = convert(args.first(), name, stringify!($member))?;
+                                self.$member_write (member);
+                                Ok(None)
+                            },)*)*
                             $(stringify!($read_slot) => {
-                                Some(self.$read_slot ())
+                                Ok(Some(self.$read_slot ().clone()))
                             },
                             stringify!($write_slot) => {
-                                let mut iter = args.into_iter();
-                                let next = next_or_panic (iter.next());
-                                let property: $proptype = next.into();
+                                let property: $proptype = convert(args.first(), name, stringify!($propname))?;
                                 self.$write_slot (property);
-                                None
+                                Ok(None)
                             },)*
-                            _ => panic!("Unrecognized slot call: {}", name)
+                            _ => Err(format!("Unrecognized slot call: {}", name))
                         }
                     }
 
@@ -203,27 +360,37 @@ macro_rules! Q_OBJECT{
                             let mut mttypes = Vec::new();
                             $(
                                 argc += 1;
-                                mttypes.push($signalqtype::metatype() as i32);
+                                mttypes.push(<$signalqtype as QMetaTypable>::metatype() as i32);
                             )*
                             signals.push((stringify!($signalname), argc, mttypes));
                         )*
+                        $(signals.push((stringify!($notify_sig), 0, Vec::new()));)*
+                        $($(signals.push((stringify!($member_notify), 0, Vec::new()));)*)*
                         let mut slots = Vec::new();
                         $(
                             let mut argc = 0;
                             let mut mttypes = Vec::new();
                             $(
                                 argc += 1;
-                                mttypes.push($slotqtype::metatype() as i32);
+                                mttypes.push(<$slotqtype as QMetaTypable>::metatype() as i32);
                             )*
-                            slots.push((stringify!($slotname), 43, argc, mttypes));
+                            slots.push((stringify!($slotname), __slot_return_metatype!($($slotret)*), argc, mttypes));
                         )*
                         $(
-                            slots.push((stringify!($read_slot), $proptype::metatype() as i32, 0, Vec::new()));
-                            slots.push((stringify!($write_slot), QMetaType::Void as i32, 1, vec![$proptype::metatype() as i32]));
+                            slots.push((stringify!($member_read), <$membertype as QMetaTypable>::metatype() as i32, 0, Vec::new()));
+                            $(slots.push((stringify!($member_write), QMetaType::Void as i32, 1, vec![<$membertype as QMetaTypable>::metatype() as i32]));)*
+                        )*
+                        $(
+                            slots.push((stringify!($read_slot), <$proptype as QMetaTypable>::metatype() as i32, 0, Vec::new()));
+                            slots.push((stringify!($write_slot), QMetaType::Void as i32, 1, vec![<$proptype as QMetaTypable>::metatype() as i32]));
                         )*
                         let mut props: Vec<(&'static str, i32, &'static str, &'static str, &'static str)> = Vec::new();
                         $(
-                            props.push((stringify!($propname), $proptype::metatype() as i32, stringify!($read_slot),
+                            props.push((stringify!($member), <$membertype as QMetaTypable>::metatype() as i32, stringify!($member_read),
+                            __member_name!($($member_write)*), __member_name!($($member_notify)*)));
+                        )*
+                        $(
+                            props.push((stringify!($propname), <$proptype as QMetaTypable>::metatype() as i32, stringify!($read_slot),
                             stringify!($write_slot), stringify!($notify_sig)));
                         )*
                         (signals, slots, props, stringify!($obj))
@@ -236,8 +403,39 @@ macro_rules! Q_OBJECT{
                     fn get_qobj_mut(&mut self) -> &mut QObject{
                         &mut self.ptr
                     }
+
+                    fn as_any(&self) -> &::std::any::Any{
+                        self
+                    }
+
+                    fn as_any_mut(&mut self) -> &mut ::std::any::Any{
+                        self
+                    }
                 }
             };
+    (
+        pub $obj:ident as $wrapper:ident{
+            signals:
+            $(fn $signalname:ident ( $( $signalvar:ident : $signalqtype:ty ),* );)*
+
+            slots:
+            $(fn $slotname:ident ( $( $slotvar:ident : $slotqtype:ty ),* ) $(-> $slotret:ty)*;)*
+
+            properties:
+            $($propname:ident : $proptype:ty; read: $read_slot:ident, write: $write_slot:ident,
+                notify: $notify_sig:ident;)*
+            }) =>{
+                Q_OBJECT!(
+                pub $obj as $wrapper{
+                    signals:
+                    $(fn $signalname ( $( $signalvar : $signalqtype ),* );)*
+                    slots:
+                    $(fn $slotname ( $( $slotvar : $slotqtype ),* ) $(-> $slotret)*;)*
+                    members:
+                    properties:
+                    $($propname : $proptype; read: $read_slot, write: $write_slot, notify: $notify_sig;)*
+                });
+            };
         }
 
 /// Generates a wrapper for [`QListModel`](struct.QListModel.html) for static typing and easier management.
@@ -320,7 +518,8 @@ macro_rules! Q_LISTMODEL{
                         view.into_iter().map(|v| {
                             let mut v = v.iter();
                             $(
-                                let $rolename = v.next().unwrap().into();
+                                let $rolename: $roletype = ::std::convert::TryFrom::try_from(v.next().unwrap())
+                                    .expect(concat!("Wrong type of a role `", stringify!($rolename), "`"));
                             )*
                             ($($rolename),*)
                         }).collect()
@@ -5,6 +5,7 @@ use qvariant::*;
 use types::*;
 use qmodelindex::*;
 use qinthasharray::*;
+use qpanic::*;
 
 extern "C" {
 
@@ -161,12 +162,16 @@ impl<'a, T : QModel> QModel for QAbstractListModel<'a, T> {
     }
 }
 
+fn model_location<T>(callback: &str) -> String {
+    format!("{} of a model `{}`", callback, ::std::any::type_name::<T>())
+}
+
 extern "C" fn row_count_callback<T : QModel>(Qself: *const libc::c_void,
                                  index: DosQModelIndex,
                                  result: *mut i32) {
     unsafe {
         let qlist = &*(Qself as *const QAbstractListModel<T>);
-        *result = qlist.row_count();
+        *result = catch_panic(|| model_location::<T>("rowCount"), || qlist.row_count()).unwrap_or(0);
     }
 }
 
@@ -177,8 +182,8 @@ extern "C" fn data_callback<T : QModel>(Qself: *const libc::c_void,
     let qindex: QModelIndex = index.into();
     unsafe {
         let qlist = &*(Qself as *const QAbstractListModel<T>);
-        let mut qvar: QVariant = result.into();
-        qvar.set(&qlist.data(qindex, role));
+        catch_panic(|| model_location::<T>("data"),
+                    || assign_qvariant(result, &qlist.data(qindex, role)));
     }
 }
 
@@ -186,9 +191,11 @@ extern "C" fn role_names_callback<T : QModel>(Qself: *const libc::c_void, result
     unsafe {
         let qlist = &*(Qself as *const QAbstractListModel<T>);
         let hash: QHashIntQByteArray = result.into();
-        for (i, name) in qlist.roles_names().iter().enumerate() {
-            hash.insert(START_ROLE + i as i32, name);
-        }
+        catch_panic(|| model_location::<T>("roleNames"), || {
+            for (i, name) in qlist.roles_names().iter().enumerate() {
+                hash.insert(START_ROLE + i as i32, name);
+            }
+        });
     }
 }
 
@@ -243,7 +250,9 @@ extern "C" fn RustRowCountCallback(Qself: *const libc::c_void,
                                    result: *mut i32) {
     unsafe {
         let qlist = &*(Qself as *const QListModel);
-        *result = qlist.row_count() as i32;
+        *result = catch_panic(|| "rowCount of a `QListModel`".to_string(),
+                              || qlist.row_count() as i32)
+            .unwrap_or(0);
     }
 }
 
@@ -288,9 +297,10 @@ extern "C" fn RustDataCallback(Qself: *const libc::c_void,
     let qindex: QModelIndex = index.into();
     unsafe {
         let qlist = &*(Qself as *const QListModel);
-        let data = &qlist.model[qindex.row() as usize][(role - START_ROLE) as usize];
-        let mut qvar: QVariant = result.into();
-        qvar.set(data);
+        catch_panic(|| "data of a `QListModel`".to_string(), || {
+            let data = &qlist.model[qindex.row() as usize][(role - START_ROLE) as usize];
+            assign_qvariant(result, data);
+        });
     }
 }
 
@@ -299,9 +309,11 @@ extern "C" fn RustRoleNamesCallback(Qself: *const libc::c_void, result: MutDosQH
     unsafe {
         let qlist = &*(Qself as *const QListModel);
         let hash: QHashIntQByteArray = result.into();
-        for (i, name) in qlist.rolenames.iter().enumerate() {
-            hash.insert(START_ROLE + i as i32, name);
-        }
+        catch_panic(|| "roleNames of a `QListModel`".to_string(), || {
+            for (i, name) in qlist.rolenames.iter().enumerate() {
+                hash.insert(START_ROLE + i as i32, name);
+            }
+        });
     }
 }
 
@@ -0,0 +1,79 @@
+use std::convert::TryFrom;
+use std::ops::{Deref, DerefMut};
+
+use qvariant::*;
+use qtypes::*;
+
+/// Binary data, passed to QML as [`QByteArray`](http://doc.qt.io/qt-5/qbytearray.html),
+/// i.e. an `ArrayBuffer` in JavaScript.
+///
+/// `Vec<u8>` and `&[u8]` are passed the same way. The newtype is handy where a distinct type is wanted,
+/// e.g. to implement own traits for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct QByteArray(pub Vec<u8>);
+
+impl Deref for QByteArray {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for QByteArray {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for QByteArray {
+    fn from(i: Vec<u8>) -> Self {
+        QByteArray(i)
+    }
+}
+
+impl<'a> From<&'a [u8]> for QByteArray {
+    fn from(i: &'a [u8]) -> Self {
+        QByteArray(i.to_vec())
+    }
+}
+
+impl From<QByteArray> for Vec<u8> {
+    fn from(i: QByteArray) -> Self {
+        i.0
+    }
+}
+
+impl<'a> From<&'a QByteArray> for QVariant {
+    fn from(i: &'a QByteArray) -> Self {
+        QVariant::from(i.as_slice())
+    }
+}
+
+impl From<QByteArray> for QVariant {
+    fn from(i: QByteArray) -> Self {
+        QVariant::from(&i)
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QByteArray {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        Vec::try_from(i).map(QByteArray)
+    }
+}
+
+impl TryFrom<QVariant> for QByteArray {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QByteArray::try_from(&i)
+    }
+}
+
+impl QMetaTypable for QByteArray {
+    fn metatype() -> QMetaType {
+        QMetaType::QByteArray
+    }
+}
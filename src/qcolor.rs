@@ -0,0 +1,98 @@
+use std::convert::TryFrom;
+
+use types::*;
+use qvariant::*;
+use qtypes::*;
+
+extern "C" {
+    fn dosext_qvariant_create_color(red: i32, green: i32, blue: i32, alpha: i32) -> DosQVariant;
+    fn dosext_qvariant_toColor(val: DosQVariant,
+                               red: *mut i32,
+                               green: *mut i32,
+                               blue: *mut i32,
+                               alpha: *mut i32)
+                               -> bool;
+}
+
+/// Analogue of [`QColor`](http://doc.qt.io/qt-5/qcolor.html), a `color` in QML.
+///
+/// Can be bound directly to properties like `Rectangle.color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct QColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Alpha channel, `255` is fully opaque
+    pub a: u8,
+}
+
+impl QColor {
+    /// Creates an opaque color.
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        QColor::rgba(r, g, b, 255)
+    }
+
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        QColor {
+            r: r,
+            g: g,
+            b: b,
+            a: a,
+        }
+    }
+}
+
+impl Default for QColor {
+    /// Opaque black.
+    fn default() -> Self {
+        QColor::rgb(0, 0, 0)
+    }
+}
+
+impl QVariant {
+    /// Returns a color, if this `QVariant` holds a `QColor` or a valid color name, like `"red"` or `"#ff0000"`.
+    pub fn to_color(&self) -> Option<QColor> {
+        match self.metatype() {
+            Some(QMetaType::QColor) |
+            Some(QMetaType::QString) => unsafe {
+                let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+                if dosext_qvariant_toColor(get_private_variant(self), &mut r, &mut g, &mut b, &mut a) {
+                    Some(QColor::rgba(r as u8, g as u8, b as u8, a as u8))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<QColor> for QVariant {
+    fn from(i: QColor) -> Self {
+        unsafe {
+            new_qvar(dosext_qvariant_create_color(i.r as i32, i.g as i32, i.b as i32, i.a as i32))
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QColor {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_color().ok_or_else(|| QVariantConversionError::mismatch("QColor", i))
+    }
+}
+
+impl TryFrom<QVariant> for QColor {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QColor::try_from(&i)
+    }
+}
+
+impl QMetaTypable for QColor {
+    fn metatype() -> QMetaType {
+        QMetaType::QColor
+    }
+}
@@ -0,0 +1,350 @@
+use libc;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::slice::from_raw_parts;
+
+use qvariant::*;
+use qobject::*;
+use qpanic::*;
+use qmeta::*;
+use types::*;
+
+extern "C" {
+    fn dos_chararray_delete(ptr: DosCStr);
+    fn dos_qobject_signal_connect(senderVPtr: DosQObject,
+                                  signal: *const libc::c_char,
+                                  receiverVPtr: DosQObject,
+                                  method: *const libc::c_char,
+                                  qtype: i32)
+                                  -> bool;
+    fn dos_qobject_connect_lambda_static(sender: DosQObject,
+                                         signal: DosCStr,
+                                         callback: ConnectLambdaCallback,
+                                         callbackData: *mut libc::c_void,
+                                         connectionType: i32)
+                                         -> DosQMetaObjectConnection;
+    fn dos_qobject_disconnect_with_connection_static(connection: DosQMetaObjectConnection);
+    fn dos_qmetaobject_connection_delete(connection: DosQMetaObjectConnection);
+
+    fn dosext_qobject_methodSignature(vptr: DosQObject, name: DosCStr, signal: bool) -> DosCStr;
+}
+
+/// Called when a signal, connected to a closure, is emitted
+/// @param callbackData The pointer passed on connection
+/// @param argc The number of arguments
+/// @param argv An array of `DosQVariant` pointers. They should not be deleted
+type ConnectLambdaCallback = extern "C" fn(*mut libc::c_void, i32, *mut DosQVariant);
+
+/// An error of connecting a signal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionError {
+    /// The sender has no signal with this name.
+    NoSuchSignal(String),
+    /// The receiver has no slot with this name.
+    NoSuchSlot(String),
+    /// The slot can't receive arguments of the signal.
+    IncompatibleArguments {
+        /// Name of the signal
+        signal: String,
+        /// Name of the slot
+        slot: String,
+    },
+    /// Qt refused to make a connection.
+    Failed,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConnectionError::NoSuchSignal(ref signal) => write!(f, "there is no signal `{}`", signal),
+            ConnectionError::NoSuchSlot(ref slot) => write!(f, "there is no slot `{}`", slot),
+            ConnectionError::IncompatibleArguments { ref signal, ref slot } => {
+                write!(f, "slot `{}` can't receive arguments of signal `{}`", slot, signal)
+            }
+            ConnectionError::Failed => write!(f, "Qt failed to make a connection"),
+        }
+    }
+}
+
+impl Error for ConnectionError {}
+
+/// A closure, that can handle a signal. Implemented for closures taking up to 6 arguments,
+/// which are converted from `QVariant` with `TryFrom<&QVariant>`.
+///
+/// Types of the arguments should be annotated, like in `|x: i32, name: String| ...`.
+/// A handler may take less arguments than the signal has, the rest are ignored.
+///
+/// A signal may be emitted from any thread, even from several at once, so a handler should be `Send` and `Sync`,
+/// and keeps its state in atomics or behind a `Mutex`.
+pub trait SignalHandler<Args>: Send + Sync {
+    /// Converts arguments of a signal and calls the handler.
+    fn handle(&self, args: &[QVariantRef]) -> Result<(), String>;
+}
+
+macro_rules! signal_handlers {
+    ($(($($arg:ident: $t:ident),*))*) => {
+        $(
+            impl<F, $($t),*> SignalHandler<($($t,)*)> for F
+                where F: Fn($($t),*) + Send + Sync
+                      $(, $t: for<'a> TryFrom<&'a QVariant, Error = QVariantConversionError>)*
+            {
+                fn handle(&self, args: &[QVariantRef]) -> Result<(), String> {
+                    let iter = &mut args.iter();
+                    $(
+                        let $arg: $t = match iter.next() {
+                            Some(qvar) => TryFrom::try_from(&**qvar).map_err(|e| {
+                                format!("Wrong argument `{}`: {}", stringify!($arg), e)
+                            })?,
+                            None => return Err(format!("Not enough arguments, `{}` is missing", stringify!($arg))),
+                        };
+                    )*
+                    self($($arg),*);
+                    Ok(())
+                }
+            }
+        )*
+    }
+}
+
+signal_handlers! {
+    ()
+    (a: A)
+    (a: A, b: B)
+    (a: A, b: B, c: C)
+    (a: A, b: B, c: C, d: D)
+    (a: A, b: B, c: C, d: D, e: E)
+    (a: A, b: B, c: C, d: D, e: E, f: G)
+}
+
+struct ConnectionData {
+    signal: String,
+    handler: Box<Fn(&[QVariantRef]) -> Result<(), String> + Send + Sync>,
+}
+
+/// A connection of a signal to a closure, made with [`QObject::connect`](struct.QObject.html#method.connect)
+/// or [`QObjectRef::connect`](struct.QObjectRef.html#method.connect).
+///
+/// The signal is disconnected, when the `Connection` is dropped.
+pub struct Connection {
+    ptr: DosQMetaObjectConnection,
+    data: Box<ConnectionData>,
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connection({})", self.data.signal)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe {
+            dos_qobject_disconnect_with_connection_static(self.ptr);
+            dos_qmetaobject_connection_delete(self.ptr);
+        }
+    }
+}
+
+extern "C" fn connection_callback(data: *mut libc::c_void, argc: i32, argv: *mut DosQVariant) {
+    unsafe {
+        let data = &*(data as *const ConnectionData);
+        let args: Vec<QVariantRef> = from_raw_parts(argv, argc as usize)
+            .iter()
+            .map(|&dq| new_qvariant_ref(dq))
+            .collect();
+        let signal = &data.signal;
+        let handler = &data.handler;
+        catch_panic(|| format!("handler of a signal `{}`", signal), || {
+            if let Err(e) = handler(&args) {
+                eprintln!("Can't handle a signal `{}`: {}", signal, e);
+            }
+        });
+    }
+}
+
+/// Returns the signature of a signal or a slot in the form, that Qt uses for connections.
+fn method_signature(obj: DosQObject, name: &str, signal: bool) -> Option<CString> {
+    let name = match CString::new(name) {
+        Ok(name) => name,
+        Err(_) => return None,
+    };
+    unsafe {
+        let ch_ar = dosext_qobject_methodSignature(obj, name.as_ptr(), signal);
+        if ch_ar.is_null() {
+            return None;
+        }
+        let res = CStr::from_ptr(ch_ar).to_owned();
+        dos_chararray_delete(ch_ar);
+        Some(res)
+    }
+}
+
+fn connect_closure<Args, F>(obj: DosQObject, signal: &str, handler: F) -> Result<Connection, ConnectionError>
+    where F: SignalHandler<Args> + 'static
+{
+    let signature = method_signature(obj, signal, true).ok_or_else(|| ConnectionError::NoSuchSignal(signal.into()))?;
+    let data = Box::new(ConnectionData {
+        signal: signal.into(),
+        handler: Box::new(move |args: &[QVariantRef]| handler.handle(args)),
+    });
+    let ptr = unsafe {
+        dos_qobject_connect_lambda_static(obj,
+                                          signature.as_ptr(),
+                                          connection_callback,
+                                          &*data as *const ConnectionData as *mut libc::c_void,
+                                          QtConnectionType::Auto as i32)
+    };
+    if ptr.is_null() {
+        return Err(ConnectionError::Failed);
+    }
+    Ok(Connection {
+        ptr: ptr,
+        data: data,
+    })
+}
+
+impl QObject {
+    /// Connects a signal of this object to a closure, see [`SignalHandler`](trait.SignalHandler.html).
+    ///
+    /// The closure is called in the thread, that emits the signal, until the returned `Connection` is dropped.
+    /// That's why it should be `Send` and `Sync`, like a closure passed to `thread::spawn`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let connection = qobject.get_qobj().connect("name_changed", || println!("The name has changed"))?;
+    /// ```
+    pub fn connect<Args, F>(&self, signal: &str, handler: F) -> Result<Connection, ConnectionError>
+        where F: SignalHandler<Args> + 'static
+    {
+        connect_closure(get_qobj_ptr(self), signal, handler)
+    }
+}
+
+impl<'a> QObjectRef<'a> {
+    /// Connects a signal of this object, for example one defined in QML, to a closure.
+    /// Works like [`QObject::connect`](struct.QObject.html#method.connect).
+    pub fn connect<Args, F>(&self, signal: &str, handler: F) -> Result<Connection, ConnectionError>
+        where F: SignalHandler<Args> + 'static
+    {
+        connect_closure(get_qobject_ref_ptr(self), signal, handler)
+    }
+}
+
+/// Checks that the sender has the signal and the receiver has the slot, which takes the same types of arguments
+/// as the signal, though it may take less of them.
+fn check_signature(sender: &QMetaDef, signal: &str, receiver: &QMetaDef, slot: &str) -> Result<(), ConnectionError> {
+    let signal_types = match sender.0.iter().find(|s| s.0 == signal) {
+        Some(&(_, _, ref types)) => types,
+        None => return Err(ConnectionError::NoSuchSignal(signal.into())),
+    };
+    let slot_types = match receiver.1.iter().find(|s| s.0 == slot) {
+        Some(&(_, _, _, ref types)) => types,
+        None => return Err(ConnectionError::NoSuchSlot(slot.into())),
+    };
+    if slot_types.len() > signal_types.len() || slot_types[..] != signal_types[..slot_types.len()] {
+        return Err(ConnectionError::IncompatibleArguments {
+            signal: signal.into(),
+            slot: slot.into(),
+        });
+    }
+    Ok(())
+}
+
+/// Connects a signal of one object to a slot of another, like `QObject::connect` in Qt.
+///
+/// Both objects are wrappers, generated by [`Q_OBJECT`](macro.Q_OBJECT!.html). Before connecting,
+/// the slot is checked to take the same types of arguments as the signal, though it may take less of them.
+/// The connection lives until one of the objects is destroyed.
+///
+/// `qtype` is a [`QtConnectionType`](enum.QtConnectionType.html), or one combined with Qt::UniqueConnection,
+/// so connecting the same signal to the same slot again fails.
+///
+/// # Examples
+///
+/// ```ignore
+/// connect(&*sender, "valueChanged", &*receiver, "onValue", QtConnectionType::Queued)?;
+/// connect(&*sender, "reset", &*receiver, "refresh", QtConnectionType::Auto.unique())?;
+/// ```
+pub fn connect<C: Into<QtConnectionFlags>>(sender: &QObjectMacro,
+                                           signal: &str,
+                                           receiver: &QObjectMacro,
+                                           slot: &str,
+                                           qtype: C)
+                                           -> Result<(), ConnectionError> {
+    check_signature(&sender.qmeta(), signal, &receiver.qmeta(), slot)?;
+
+    let qtype = connection_flags_bits(qtype.into());
+    let sender = get_qobj_ptr(sender.get_qobj());
+    let receiver = get_qobj_ptr(receiver.get_qobj());
+    let signal = method_signature(sender, signal, true).ok_or_else(|| ConnectionError::NoSuchSignal(signal.into()))?;
+    let slot = method_signature(receiver, slot, false).ok_or_else(|| ConnectionError::NoSuchSlot(slot.into()))?;
+    if unsafe { dos_qobject_signal_connect(sender, signal.as_ptr(), receiver, slot.as_ptr(), qtype) } {
+        Ok(())
+    } else {
+        Err(ConnectionError::Failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qtypes::*;
+
+    fn sender() -> QMetaDef {
+        (vec![("valueChanged", 2, vec![QMetaType::Int as i32, QMetaType::QString as i32]), ("reset", 0, vec![])],
+         vec![],
+         vec![],
+         "Sender")
+    }
+
+    fn receiver() -> QMetaDef {
+        (vec![],
+         vec![("onValue", QMetaType::Void as i32, 2, vec![QMetaType::Int as i32, QMetaType::QString as i32]),
+              ("onNumber", QMetaType::Void as i32, 1, vec![QMetaType::Int as i32]),
+              ("onName", QMetaType::Void as i32, 1, vec![QMetaType::QString as i32]),
+              ("refresh", QMetaType::Void as i32, 0, vec![])],
+         vec![],
+         "Receiver")
+    }
+
+    #[test]
+    fn slots_may_take_a_prefix_of_signal_arguments() {
+        assert_eq!(check_signature(&sender(), "valueChanged", &receiver(), "onValue"), Ok(()));
+        assert_eq!(check_signature(&sender(), "valueChanged", &receiver(), "onNumber"), Ok(()));
+        assert_eq!(check_signature(&sender(), "valueChanged", &receiver(), "refresh"), Ok(()));
+        assert_eq!(check_signature(&sender(), "reset", &receiver(), "refresh"), Ok(()));
+    }
+
+    #[test]
+    fn incompatible_slots_are_rejected() {
+        let incompatible = |signal: &str, slot: &str| {
+            Err(ConnectionError::IncompatibleArguments {
+                signal: signal.into(),
+                slot: slot.into(),
+            })
+        };
+        assert_eq!(check_signature(&sender(), "valueChanged", &receiver(), "onName"),
+                   incompatible("valueChanged", "onName"));
+        assert_eq!(check_signature(&sender(), "reset", &receiver(), "onNumber"),
+                   incompatible("reset", "onNumber"));
+    }
+
+    #[test]
+    fn unique_connections_set_the_flag() {
+        assert_eq!(connection_flags_bits(QtConnectionType::Queued.into()), 2);
+        assert_eq!(connection_flags_bits(QtConnectionType::Queued.unique()), 0x82);
+        assert_eq!(connection_flags_bits(QtConnectionType::Auto.unique()), 0x80);
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(check_signature(&sender(), "clicked", &receiver(), "refresh"),
+                   Err(ConnectionError::NoSuchSignal("clicked".into())));
+        assert_eq!(check_signature(&sender(), "reset", &receiver(), "valueChanged"),
+                   Err(ConnectionError::NoSuchSlot("valueChanged".into())));
+        assert_eq!(ConnectionError::NoSuchSlot("x".into()).to_string(), "there is no slot `x`");
+    }
+}
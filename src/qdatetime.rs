@@ -0,0 +1,471 @@
+use std::convert::TryFrom;
+
+use types::*;
+use qvariant::*;
+use qtypes::*;
+
+extern "C" {
+    fn dosext_qvariant_create_date(year: i32, month: i32, day: i32) -> DosQVariant;
+    fn dosext_qvariant_create_time(hour: i32, minute: i32, second: i32, msec: i32) -> DosQVariant;
+    fn dosext_qvariant_create_datetime(year: i32,
+                                       month: i32,
+                                       day: i32,
+                                       hour: i32,
+                                       minute: i32,
+                                       second: i32,
+                                       msec: i32,
+                                       offsetFromUtc: i32)
+                                       -> DosQVariant;
+    fn dosext_qvariant_toDate(val: DosQVariant,
+                              year: *mut i32,
+                              month: *mut i32,
+                              day: *mut i32)
+                              -> bool;
+    fn dosext_qvariant_toTime(val: DosQVariant,
+                              hour: *mut i32,
+                              minute: *mut i32,
+                              second: *mut i32,
+                              msec: *mut i32)
+                              -> bool;
+    fn dosext_qvariant_toDateTime(val: DosQVariant,
+                                  year: *mut i32,
+                                  month: *mut i32,
+                                  day: *mut i32,
+                                  hour: *mut i32,
+                                  minute: *mut i32,
+                                  second: *mut i32,
+                                  msec: *mut i32,
+                                  offsetFromUtc: *mut i32)
+                                  -> bool;
+}
+
+/// Analogue of [`QDate`](http://doc.qt.io/qt-5/qdate.html), a `Date` without time in QML.
+///
+/// A default value is a null date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct QDate {
+    pub year: i32,
+    /// Month, starting from `1`
+    pub month: u32,
+    /// Day of the month, starting from `1`
+    pub day: u32,
+}
+
+impl QDate {
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        QDate {
+            year: year,
+            month: month,
+            day: day,
+        }
+    }
+}
+
+/// Analogue of [`QTime`](http://doc.qt.io/qt-5/qtime.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct QTime {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// Milliseconds
+    pub msec: u32,
+}
+
+impl QTime {
+    pub fn new(hour: u32, minute: u32, second: u32, msec: u32) -> Self {
+        QTime {
+            hour: hour,
+            minute: minute,
+            second: second,
+            msec: msec,
+        }
+    }
+}
+
+/// Analogue of [`QDateTime`](http://doc.qt.io/qt-5/qdatetime.html), a `Date` in QML.
+///
+/// Date and time are local to the offset from UTC, so QML can display them in any time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct QDateTime {
+    pub date: QDate,
+    pub time: QTime,
+    /// Offset from UTC in seconds
+    pub offset: i32,
+}
+
+impl QDateTime {
+    pub fn new(date: QDate, time: QTime, offset: i32) -> Self {
+        QDateTime {
+            date: date,
+            time: time,
+            offset: offset,
+        }
+    }
+}
+
+impl QVariant {
+    /// Returns a date, if this `QVariant` holds a valid `QDate` or `QDateTime`.
+    pub fn to_date(&self) -> Option<QDate> {
+        match self.metatype() {
+            Some(QMetaType::QDate) |
+            Some(QMetaType::QDateTime) => unsafe {
+                let (mut year, mut month, mut day) = (0, 0, 0);
+                if dosext_qvariant_toDate(get_private_variant(self), &mut year, &mut month, &mut day) {
+                    Some(QDate::new(year, month as u32, day as u32))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns a time, if this `QVariant` holds a valid `QTime` or `QDateTime`.
+    pub fn to_time(&self) -> Option<QTime> {
+        match self.metatype() {
+            Some(QMetaType::QTime) |
+            Some(QMetaType::QDateTime) => unsafe {
+                let (mut hour, mut minute, mut second, mut msec) = (0, 0, 0, 0);
+                if dosext_qvariant_toTime(get_private_variant(self),
+                                          &mut hour,
+                                          &mut minute,
+                                          &mut second,
+                                          &mut msec) {
+                    Some(QTime::new(hour as u32, minute as u32, second as u32, msec as u32))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns a date and time, if this `QVariant` holds a valid `QDateTime`.
+    pub fn to_datetime(&self) -> Option<QDateTime> {
+        match self.metatype() {
+            Some(QMetaType::QDateTime) => unsafe {
+                let (mut year, mut month, mut day) = (0, 0, 0);
+                let (mut hour, mut minute, mut second, mut msec) = (0, 0, 0, 0);
+                let mut offset = 0;
+                if dosext_qvariant_toDateTime(get_private_variant(self),
+                                              &mut year,
+                                              &mut month,
+                                              &mut day,
+                                              &mut hour,
+                                              &mut minute,
+                                              &mut second,
+                                              &mut msec,
+                                              &mut offset) {
+                    Some(QDateTime::new(QDate::new(year, month as u32, day as u32),
+                                        QTime::new(hour as u32, minute as u32, second as u32, msec as u32),
+                                        offset))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<QDate> for QVariant {
+    fn from(i: QDate) -> Self {
+        unsafe {
+            new_qvar(dosext_qvariant_create_date(i.year, i.month as i32, i.day as i32))
+        }
+    }
+}
+
+impl From<QTime> for QVariant {
+    fn from(i: QTime) -> Self {
+        unsafe {
+            new_qvar(dosext_qvariant_create_time(i.hour as i32,
+                                                 i.minute as i32,
+                                                 i.second as i32,
+                                                 i.msec as i32))
+        }
+    }
+}
+
+impl From<QDateTime> for QVariant {
+    fn from(i: QDateTime) -> Self {
+        unsafe {
+            new_qvar(dosext_qvariant_create_datetime(i.date.year,
+                                                     i.date.month as i32,
+                                                     i.date.day as i32,
+                                                     i.time.hour as i32,
+                                                     i.time.minute as i32,
+                                                     i.time.second as i32,
+                                                     i.time.msec as i32,
+                                                     i.offset))
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QDate {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_date().ok_or_else(|| QVariantConversionError::mismatch("QDate", i))
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QTime {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_time().ok_or_else(|| QVariantConversionError::mismatch("QTime", i))
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QDateTime {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_datetime().ok_or_else(|| QVariantConversionError::mismatch("QDateTime", i))
+    }
+}
+
+impl TryFrom<QVariant> for QDate {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QDate::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for QTime {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QTime::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for QDateTime {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QDateTime::try_from(&i)
+    }
+}
+
+impl QMetaTypable for QDate {
+    fn metatype() -> QMetaType {
+        QMetaType::QDate
+    }
+}
+
+impl QMetaTypable for QTime {
+    fn metatype() -> QMetaType {
+        QMetaType::QTime
+    }
+}
+
+impl QMetaTypable for QDateTime {
+    fn metatype() -> QMetaType {
+        QMetaType::QDateTime
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use std::convert::TryFrom;
+    use chrono::{Datelike, DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+
+    use qtypes::*;
+    use super::*;
+
+    fn out_of_range<T: ::std::fmt::Debug>(expected: &'static str, value: T) -> QVariantConversionError {
+        QVariantConversionError::OutOfRange {
+            expected: expected,
+            value: format!("{:?}", value),
+        }
+    }
+
+    fn to_qdate(i: &NaiveDate) -> QDate {
+        QDate::new(i.year(), i.month(), i.day())
+    }
+
+    fn to_qtime(i: &NaiveTime) -> QTime {
+        // Leap seconds are represented with more than a second of nanoseconds in chrono
+        let msec = ::std::cmp::min(i.nanosecond() / 1_000_000, 999);
+        QTime::new(i.hour(), i.minute(), i.second(), msec)
+    }
+
+    fn from_qdate(i: QDate) -> Result<NaiveDate, QVariantConversionError> {
+        NaiveDate::from_ymd_opt(i.year, i.month, i.day).ok_or_else(|| out_of_range("NaiveDate", i))
+    }
+
+    fn from_qtime(i: QTime) -> Result<NaiveTime, QVariantConversionError> {
+        NaiveTime::from_hms_milli_opt(i.hour, i.minute, i.second, i.msec)
+            .ok_or_else(|| out_of_range("NaiveTime", i))
+    }
+
+    fn from_qdatetime(i: QDateTime) -> Result<DateTime<FixedOffset>, QVariantConversionError> {
+        let naive = from_qdate(i.date)?.and_time(from_qtime(i.time)?);
+        FixedOffset::east_opt(i.offset)
+            .and_then(|offset| offset.from_local_datetime(&naive).single())
+            .ok_or_else(|| out_of_range("DateTime", i))
+    }
+
+    impl From<NaiveDate> for QVariant {
+        fn from(i: NaiveDate) -> Self {
+            to_qdate(&i).into()
+        }
+    }
+
+    impl From<NaiveTime> for QVariant {
+        fn from(i: NaiveTime) -> Self {
+            to_qtime(&i).into()
+        }
+    }
+
+    impl From<DateTime<FixedOffset>> for QVariant {
+        fn from(i: DateTime<FixedOffset>) -> Self {
+            let local = i.naive_local();
+            QDateTime::new(to_qdate(&local.date()),
+                           to_qtime(&local.time()),
+                           i.offset().local_minus_utc())
+                .into()
+        }
+    }
+
+    impl From<DateTime<Utc>> for QVariant {
+        fn from(i: DateTime<Utc>) -> Self {
+            QVariant::from(i.with_timezone(&FixedOffset::east_opt(0).unwrap()))
+        }
+    }
+
+    impl<'a> TryFrom<&'a QVariant> for NaiveDate {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+            from_qdate(QDate::try_from(i)?)
+        }
+    }
+
+    impl<'a> TryFrom<&'a QVariant> for NaiveTime {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+            from_qtime(QTime::try_from(i)?)
+        }
+    }
+
+    impl<'a> TryFrom<&'a QVariant> for DateTime<FixedOffset> {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+            from_qdatetime(QDateTime::try_from(i)?)
+        }
+    }
+
+    impl<'a> TryFrom<&'a QVariant> for DateTime<Utc> {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+            Ok(from_qdatetime(QDateTime::try_from(i)?)?.with_timezone(&Utc))
+        }
+    }
+
+    impl TryFrom<QVariant> for NaiveDate {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+            NaiveDate::try_from(&i)
+        }
+    }
+
+    impl TryFrom<QVariant> for NaiveTime {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+            NaiveTime::try_from(&i)
+        }
+    }
+
+    impl TryFrom<QVariant> for DateTime<FixedOffset> {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+            DateTime::try_from(&i)
+        }
+    }
+
+    impl TryFrom<QVariant> for DateTime<Utc> {
+        type Error = QVariantConversionError;
+
+        fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+            DateTime::try_from(&i)
+        }
+    }
+
+    impl QMetaTypable for NaiveDate {
+        fn metatype() -> QMetaType {
+            QMetaType::QDate
+        }
+    }
+
+    impl QMetaTypable for NaiveTime {
+        fn metatype() -> QMetaType {
+            QMetaType::QTime
+        }
+    }
+
+    impl QMetaTypable for DateTime<FixedOffset> {
+        fn metatype() -> QMetaType {
+            QMetaType::QDateTime
+        }
+    }
+
+    impl QMetaTypable for DateTime<Utc> {
+        fn metatype() -> QMetaType {
+            QMetaType::QDateTime
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn dates_and_times_are_copied_field_by_field() {
+            let date = NaiveDate::from_ymd_opt(2018, 2, 28).unwrap();
+            assert_eq!(to_qdate(&date), QDate::new(2018, 2, 28));
+            assert_eq!(from_qdate(QDate::new(2018, 2, 28)), Ok(date));
+
+            let time = NaiveTime::from_hms_milli_opt(13, 5, 9, 250).unwrap();
+            assert_eq!(to_qtime(&time), QTime::new(13, 5, 9, 250));
+            assert_eq!(from_qtime(QTime::new(13, 5, 9, 250)), Ok(time));
+        }
+
+        #[test]
+        fn leap_seconds_are_clamped() {
+            let time = NaiveTime::from_hms_milli_opt(23, 59, 59, 1500).unwrap();
+            assert_eq!(to_qtime(&time), QTime::new(23, 59, 59, 999));
+        }
+
+        #[test]
+        fn invalid_dates_and_times_are_out_of_range() {
+            assert_eq!(from_qdate(QDate::new(2018, 2, 30)),
+                       Err(out_of_range("NaiveDate", QDate::new(2018, 2, 30))));
+            assert!(from_qdate(QDate::default()).is_err());
+            assert_eq!(from_qtime(QTime::new(24, 0, 0, 0)),
+                       Err(out_of_range("NaiveTime", QTime::new(24, 0, 0, 0))));
+        }
+
+        #[test]
+        fn datetimes_keep_their_offset() {
+            let qdatetime = QDateTime::new(QDate::new(2018, 2, 28), QTime::new(13, 5, 9, 0), 3600);
+            let datetime = from_qdatetime(qdatetime).unwrap();
+            assert_eq!(datetime.offset().local_minus_utc(), 3600);
+            assert_eq!(datetime.with_timezone(&Utc).hour(), 12);
+
+            let bad_offset = QDateTime::new(qdatetime.date, qdatetime.time, 86400);
+            assert_eq!(from_qdatetime(bad_offset), Err(out_of_range("DateTime", bad_offset)));
+            let bad_date = QDateTime::new(QDate::default(), qdatetime.time, 0);
+            assert!(from_qdatetime(bad_date).is_err());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::convert::TryFrom;
+
+use types::*;
+use qvariant::*;
+use qtypes::*;
+
+extern "C" {
+    fn dosext_qvariant_create_pointf(x: f64, y: f64) -> DosQVariant;
+    fn dosext_qvariant_create_sizef(width: f64, height: f64) -> DosQVariant;
+    fn dosext_qvariant_create_rectf(x: f64, y: f64, width: f64, height: f64) -> DosQVariant;
+    fn dosext_qvariant_toPointF(val: DosQVariant, x: *mut f64, y: *mut f64);
+    fn dosext_qvariant_toSizeF(val: DosQVariant, width: *mut f64, height: *mut f64);
+    fn dosext_qvariant_toRectF(val: DosQVariant,
+                               x: *mut f64,
+                               y: *mut f64,
+                               width: *mut f64,
+                               height: *mut f64);
+}
+
+/// Analogue of [`QPointF`](http://doc.qt.io/qt-5/qpointf.html), a `point` in QML.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QPointF {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl QPointF {
+    pub fn new(x: f64, y: f64) -> Self {
+        QPointF { x: x, y: y }
+    }
+}
+
+/// Analogue of [`QSizeF`](http://doc.qt.io/qt-5/qsizef.html), a `size` in QML.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QSizeF {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl QSizeF {
+    pub fn new(width: f64, height: f64) -> Self {
+        QSizeF {
+            width: width,
+            height: height,
+        }
+    }
+}
+
+/// Analogue of [`QRectF`](http://doc.qt.io/qt-5/qrectf.html), a `rect` in QML.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QRectF {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl QRectF {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        QRectF {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+
+    /// Top-left corner of the rectangle.
+    pub fn origin(&self) -> QPointF {
+        QPointF::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> QSizeF {
+        QSizeF::new(self.width, self.height)
+    }
+}
+
+impl QVariant {
+    /// Returns a point, if this `QVariant` holds a `QPointF` or a `QPoint`.
+    pub fn to_pointf(&self) -> Option<QPointF> {
+        match self.metatype() {
+            Some(QMetaType::QPointF) |
+            Some(QMetaType::QPoint) => unsafe {
+                let mut point = QPointF::default();
+                dosext_qvariant_toPointF(get_private_variant(self), &mut point.x, &mut point.y);
+                Some(point)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns a size, if this `QVariant` holds a `QSizeF` or a `QSize`.
+    pub fn to_sizef(&self) -> Option<QSizeF> {
+        match self.metatype() {
+            Some(QMetaType::QSizeF) |
+            Some(QMetaType::QSize) => unsafe {
+                let mut size = QSizeF::default();
+                dosext_qvariant_toSizeF(get_private_variant(self),
+                                        &mut size.width,
+                                        &mut size.height);
+                Some(size)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns a rectangle, if this `QVariant` holds a `QRectF` or a `QRect`.
+    pub fn to_rectf(&self) -> Option<QRectF> {
+        match self.metatype() {
+            Some(QMetaType::QRectF) |
+            Some(QMetaType::QRect) => unsafe {
+                let mut rect = QRectF::default();
+                dosext_qvariant_toRectF(get_private_variant(self),
+                                        &mut rect.x,
+                                        &mut rect.y,
+                                        &mut rect.width,
+                                        &mut rect.height);
+                Some(rect)
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<QPointF> for QVariant {
+    fn from(i: QPointF) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_pointf(i.x, i.y)) }
+    }
+}
+
+impl From<QSizeF> for QVariant {
+    fn from(i: QSizeF) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_sizef(i.width, i.height)) }
+    }
+}
+
+impl From<QRectF> for QVariant {
+    fn from(i: QRectF) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_rectf(i.x, i.y, i.width, i.height)) }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QPointF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_pointf().ok_or_else(|| QVariantConversionError::mismatch("QPointF", i))
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QSizeF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_sizef().ok_or_else(|| QVariantConversionError::mismatch("QSizeF", i))
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QRectF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        i.to_rectf().ok_or_else(|| QVariantConversionError::mismatch("QRectF", i))
+    }
+}
+
+impl TryFrom<QVariant> for QPointF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QPointF::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for QSizeF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QSizeF::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for QRectF {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        QRectF::try_from(&i)
+    }
+}
+
+impl QMetaTypable for QPointF {
+    fn metatype() -> QMetaType {
+        QMetaType::QPointF
+    }
+}
+
+impl QMetaTypable for QSizeF {
+    fn metatype() -> QMetaType {
+        QMetaType::QSizeF
+    }
+}
+
+impl QMetaTypable for QRectF {
+    fn metatype() -> QMetaType {
+        QMetaType::QRectF
+    }
+}
@@ -0,0 +1,104 @@
+use libc;
+use std::marker::PhantomData;
+
+use qobject::*;
+use qmeta::*;
+use qpanic::*;
+use types::*;
+
+extern "C" {
+    fn dos_qmetaobject_invoke_method(context: DosQObject,
+                                     callback: InvokeMethodCallback,
+                                     callbackData: *mut libc::c_void,
+                                     connectionType: i32)
+                                     -> bool;
+}
+
+/// Called in the thread of the context object, when a queued invocation is delivered
+/// @param callbackData The pointer passed on invocation
+type InvokeMethodCallback = extern "C" fn(*mut libc::c_void);
+
+/// A handle to a wrapper, generated by [`Q_OBJECT`](macro.Q_OBJECT!.html), that can be sent to other threads.
+///
+/// It doesn't give access to the object directly, instead closures are queued to run in the thread of the object,
+/// usually the GUI thread, where they can update the object and emit signals.
+/// Obtained with the generated `handle()` method.
+///
+/// # Examples
+///
+/// ```ignore
+/// let handle = self.handle();
+/// thread::spawn(move || {
+///     let page = download(&url);
+///     handle.queue(move |logic: &mut QLogic| logic.pageDownloaded(page));
+/// });
+/// ```
+pub struct QObjectHandle<T> {
+    ptr: usize,
+    id: usize,
+    phantom: PhantomData<fn(&mut T)>,
+}
+
+impl<T> Clone for QObjectHandle<T> {
+    fn clone(&self) -> Self {
+        QObjectHandle {
+            ptr: self.ptr,
+            id: self.id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+type QueuedCall<T> = Box<FnOnce(&mut T) + Send>;
+
+unsafe fn drop_queued_call<T>(data: usize) {
+    drop(Box::from_raw(data as *mut QueuedCall<T>));
+}
+
+extern "C" fn queued_callback<T: QObjectMacro + 'static>(data: *mut libc::c_void) {
+    // The registry owns the call until it's taken, and drops it if the object is destroyed
+    let binded_ptr = match take_queued_call(data as usize) {
+        Some(binded_ptr) => binded_ptr as *mut &mut QObjectMacro,
+        None => return,
+    };
+    let f = unsafe { *Box::from_raw(data as *mut QueuedCall<T>) };
+    let obj: *mut T = match unsafe { (*binded_ptr).as_any_mut().downcast_mut::<T>() } {
+        Some(obj) => obj,
+        None => return,
+    };
+    unsafe {
+        catch_panic(|| format!("a closure queued to `{}`", (*obj).qmeta().3),
+                    || f(&mut *obj));
+    }
+}
+
+impl<T: QObjectMacro + 'static> QObjectHandle<T> {
+    pub fn new(obj: &T) -> Self {
+        QObjectHandle {
+            ptr: get_qobj_ptr(obj.get_qobj()) as usize,
+            id: get_qobj_id(obj.get_qobj()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Queues `f` to be called with the object in its thread, once control returns to the Qt event loop.
+    ///
+    /// Returns `false` and drops `f`, if the object has already been destroyed.
+    /// If the object is destroyed before the call is delivered, `f` is dropped without being called,
+    /// in the thread that destroys the object.
+    pub fn queue<F: FnOnce(&mut T) + Send + 'static>(&self, f: F) -> bool {
+        let call: Box<QueuedCall<T>> = Box::new(Box::new(f));
+        let data = Box::into_raw(call) as *mut libc::c_void;
+        queue_call(self.ptr as DosQObject, self.id, data as usize, drop_queued_call::<T>, || unsafe {
+            dos_qmetaobject_invoke_method(self.ptr as DosQObject,
+                                          queued_callback::<T>,
+                                          data,
+                                          QtConnectionType::Queued as i32)
+        })
+    }
+
+    /// Checks whether the object still exists. It may be destroyed right after the check, though.
+    pub fn is_alive(&self) -> bool {
+        binded_ptr_of(self.ptr as DosQObject, Some(self.id)).is_some()
+    }
+}
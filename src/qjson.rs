@@ -0,0 +1,64 @@
+use std::convert::TryFrom;
+use std::ffi::CStr;
+
+use serde_json::{self, Value};
+
+use types::*;
+use qvariant::*;
+use qtypes::*;
+
+extern "C" {
+    fn dos_chararray_delete(ptr: DosCStr);
+
+    fn dosext_qvariant_create_json(json: DosCStr, size: i32) -> DosQVariant;
+    fn dosext_qvariant_toJson(val: DosQVariant) -> DosCStr;
+}
+
+/// Produces a `QJsonValue`, which QML sees as a plain JavaScript value.
+///
+/// Note that numbers are stored as `double` in `QJsonValue`, so big integers lose precision.
+impl From<Value> for QVariant {
+    fn from(i: Value) -> Self {
+        QVariant::from(&i)
+    }
+}
+
+impl<'a> From<&'a Value> for QVariant {
+    fn from(i: &'a Value) -> Self {
+        let json = i.to_string();
+        unsafe { new_qvar(dosext_qvariant_create_json(json.as_ptr() as DosCStr, json.len() as i32)) }
+    }
+}
+
+/// Accepts `QJsonValue`, `QJsonObject`, `QJsonArray`, `QJsonDocument`
+/// and anything that `QJsonValue::fromVariant` can convert, like lists and maps.
+impl<'a> TryFrom<&'a QVariant> for Value {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        let json = unsafe {
+            let ch_ar = dosext_qvariant_toJson(get_private_variant(i));
+            if ch_ar.is_null() {
+                return Err(QVariantConversionError::mismatch("QJsonValue", i));
+            }
+            let res = CStr::from_ptr(ch_ar).to_string_lossy().into_owned();
+            dos_chararray_delete(ch_ar);
+            res
+        };
+        serde_json::from_str(&json).map_err(|_| QVariantConversionError::mismatch("QJsonValue", i))
+    }
+}
+
+impl TryFrom<QVariant> for Value {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        Value::try_from(&i)
+    }
+}
+
+impl QMetaTypable for Value {
+    fn metatype() -> QMetaType {
+        QMetaType::QJsonValue
+    }
+}
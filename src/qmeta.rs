@@ -1,4 +1,8 @@
+use std::any::Any;
+use std::ffi::CString;
+use std::fmt::Display;
 use std::mem::forget;
+use std::ptr::null_mut;
 use libc;
 
 use qvariant::*;
@@ -18,6 +22,12 @@ extern "C" {
                                name: *const libc::c_char,
                                parametersCount: i32,
                                parameters: *const DosQVariant);
+    fn dos_qobject_create(dObjectPointer: *mut libc::c_void,
+                          metaObject: DosQMetaObject,
+                          dObjectCallback: extern "C" fn(*mut libc::c_void, DosQVariant, i32, *mut DosQVariant))
+                          -> DosQObject;
+    fn dos_qobject_delete(deleted: DosQObject);
+    fn dosext_qobject_setPropertyConstant(vptr: DosQObject, name: *const libc::c_char) -> bool;
 }
 
 #[doc(hidden)]
@@ -26,9 +36,7 @@ pub fn emit_signal(obj: &QObjectMacro, signalname: &str, args: Vec<QVariant>) {
     let vec: Vec<DosQVariant> = args.iter()
         .map(|qvar| get_private_variant(qvar))
         .collect();
-    forget(args);
     unsafe {
-        println!("about to send signal");
         dos_qobject_signal_emit(get_qobj_ptr(obj.get_qobj()),
                                 stoptr(signalname),
                                 vec.len() as i32,
@@ -53,17 +61,37 @@ impl QMeta {
                                                   &def.sig_defs as *const SignalDefinitions,
                                                   &def.slot_defs as *const SlotDefinitions,
                                                   &def.prop_defs as *const PropertyDefinitions);
+            if !def.constants.is_empty() {
+                set_constant(dos_meta, &def.constants, def.name);
+            }
             QMeta { ptr: dos_meta }
         }
     }
 }
 
+extern "C" fn no_slots(_: *mut libc::c_void, _: DosQVariant, _: i32, _: *mut DosQVariant) {}
+
+/// Marks properties as `CONSTANT`. DOtherSide can't declare them, so the meta object is reached
+/// through a temporary object and patched before QML sees it.
+unsafe fn set_constant(dos_meta: DosQMetaObject, constants: &[&'static str], class: &str) {
+    let obj = dos_qobject_create(null_mut(), dos_meta, no_slots);
+    for &name in constants {
+        let cname = CString::new(name).unwrap();
+        if !dosext_qobject_setPropertyConstant(obj, cname.as_ptr()) {
+            eprintln!("Property `{}` of `{}` can't be marked as CONSTANT", name, class);
+        }
+    }
+    dos_qobject_delete(obj);
+}
+
 #[derive(Debug)]
 pub struct QMetaDefinition {
     sig_defs: SignalDefinitions,
     slot_defs: SlotDefinitions,
     prop_defs: PropertyDefinitions,
     name: &'static str,
+    // Properties without a write slot and a notify signal
+    constants: Vec<&'static str>,
 }
 
 pub fn get_qmetadef_name(o: &QMetaDefinition) -> &'static str {
@@ -77,6 +105,7 @@ pub type QMetaDef = (Vec<(&'static str, i32, Vec<i32>)>,
 impl QMetaDefinition {
     pub fn new(input: QMetaDef) -> Self {
         let (signals, slots, props, name) = input;
+        let constants = constant_properties(&props);
         let signals: Vec<SignalDefinition> = signals.into_iter()
             .map(|(s, argc, types)| {
                 let def = SignalDefinition {
@@ -133,17 +162,56 @@ impl QMetaDefinition {
             slot_defs: slot_defs,
             prop_defs: prop_defs,
             name: name,
+            constants: constants,
         }
     }
 }
 
+/// Returns names of properties, that can't change, as they have neither a write slot, nor a notify signal.
+fn constant_properties(props: &[(&'static str, i32, &'static str, &'static str, &'static str)]) -> Vec<&'static str> {
+    props.iter()
+        .filter(|&&(_, _, _, write, notify)| write.is_empty() && notify.is_empty())
+        .map(|&(name, _, _, _, _)| name)
+        .collect()
+}
+
 #[doc(hidden)]
 // Provides `qml-rust` with the neccessary information and an ability to callback slots.
 pub trait QObjectMacro {
-    fn qslot_call(&mut self, name: &str, args: Vec<QVariant>) -> Option<&QVariant>;
+    fn qslot_call(&mut self, name: &str, args: Vec<QVariantRef>) -> Result<Option<QVariant>, String>;
     fn qmeta(&self) -> QMetaDef;
     fn get_qobj(&self) -> &QObject;
     fn get_qobj_mut(&mut self) -> &mut QObject;
+    fn as_any(&self) -> &Any;
+    fn as_any_mut(&mut self) -> &mut Any;
+}
+
+/// A value, that can be returned from a slot.
+///
+/// Slots may return nothing, anything convertible into a [`QVariant`](struct.QVariant.html),
+/// or a `Result` of these. An `Err` is thrown as a JavaScript `Error` with the `Display` of the error as a message,
+/// so QML can handle it with `try/catch`.
+pub trait QSlotReturn {
+    /// Returns a value for QML, if there is one, or a message of an error.
+    fn into_slot_result(self) -> Result<Option<QVariant>, String>;
+}
+
+impl QSlotReturn for () {
+    fn into_slot_result(self) -> Result<Option<QVariant>, String> {
+        Ok(None)
+    }
+}
+
+impl<T: Into<QVariant>> QSlotReturn for T {
+    fn into_slot_result(self) -> Result<Option<QVariant>, String> {
+        Ok(Some(self.into()))
+    }
+}
+
+impl<T: QSlotReturn, E: Display> QSlotReturn for Result<T, E> {
+    fn into_slot_result(self) -> Result<Option<QVariant>, String> {
+        self.map_err(|e| e.to_string()).and_then(QSlotReturn::into_slot_result)
+    }
 }
 
 #[derive(Debug)]
@@ -193,3 +261,31 @@ struct PropertyDefinitions {
     count: i32,
     definitions: *const PropertyDefinition,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_is_returned_as_nothing() {
+        assert!(().into_slot_result().unwrap().is_none());
+        assert!(Ok::<(), String>(()).into_slot_result().unwrap().is_none());
+    }
+
+    #[test]
+    fn errors_are_returned_as_their_display() {
+        assert_eq!(Err::<i32, _>("boom").into_slot_result().unwrap_err(), "boom");
+        assert_eq!("x".parse::<i32>().into_slot_result().unwrap_err(),
+                   "invalid digit found in string");
+        let nested: Result<Result<(), String>, String> = Ok(Err("inner".into()));
+        assert_eq!(nested.into_slot_result().unwrap_err(), "inner");
+    }
+
+    #[test]
+    fn properties_without_write_and_notify_are_constant() {
+        let props = vec![("count", 2, "get_count", "set_count", "count_changed"),
+                         ("total", 2, "get_total", "", "total_changed"),
+                         ("id", 2, "get_id", "", "")];
+        assert_eq!(constant_properties(&props), vec!["id"]);
+    }
+}
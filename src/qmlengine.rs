@@ -4,6 +4,7 @@ use qvariant::*;
 use types::*;
 use qurl::*;
 use qmeta::*;
+use qpanic::{self, PanicPolicy, CallbackPanic};
 
 extern "C" {
     fn dos_qapplication_create();
@@ -85,6 +86,21 @@ impl QmlEngine {
         self.stored.push(val);
     }
 
+    /// Sets what happens when Rust code, called by Qt, panics. The default is `PanicPolicy::Abort`.
+    ///
+    /// The policy is shared by all engines in the process. `PanicPolicy::Hook` uses the hook,
+    /// set with [`set_panic_hook`](#method.set_panic_hook), and only logs panics if there is none.
+    pub fn set_panic_policy(&self, policy: PanicPolicy) {
+        qpanic::set_panic_policy(policy);
+    }
+
+    /// Registers a hook for panics in Rust code, called by Qt, and switches to `PanicPolicy::Hook`.
+    ///
+    /// The hook is shared by all engines in the process.
+    pub fn set_panic_hook<F: Fn(&CallbackPanic) + Send + Sync + 'static>(&self, hook: F) {
+        qpanic::set_panic_hook(Box::new(hook));
+    }
+
     /// Sets a property for this QML context
     pub fn set_property(&self, name: &str, value: &QVariant) {
         unsafe {
@@ -8,6 +8,7 @@ use types::*;
 use qobject::*;
 use qmeta::*;
 use utils::*;
+use qpanic::*;
 
 extern "C" {
     fn dos_qdeclarative_qmlregistertype(qmlRegisterType: *const QmlRegisterType) -> i32;
@@ -43,23 +44,29 @@ extern "C" fn create_dobject(id: i32,
                              binded_ptr: *mut *const libc::c_void,
                              dosQObject: *mut DosQObject) {
     let map = unsafe { &*(REGISTERED_TYPES.0.get()) };
-    // Getting shallow object from the map
-    let shallow = map.get(&id).unwrap();
-    // Getting pointer to a created object
-    let binded = shallow.get_new();
+    let location = || match map.get(&id) {
+        Some(shallow) => format!("creation of a registered type `{}`", shallow.qmeta().3),
+        None => format!("creation of a registered type with id {}", id),
+    };
+    // Qt expects an object to be created, so it's impossible to continue after a panic
+    catch_panic_or_abort(location, || {
+        // Getting shallow object from the map
+        let shallow = map.get(&id).unwrap();
+        // Getting pointer to a created object
+        let binded = shallow.get_new();
 
-    // Returning pointers to a wrapper and to an DosQObject, then swapping DosQObject with a fresh one
-    // Comments are copied 'as is' from the DOtherSide docs to ensure correctness
-    unsafe {
-        let mut qobj = &mut *shallow.get_qobj_from_ptr(binded);
-        // # Retrieve the DosQObject created dos_qobject_create() inside the nimQObject
-        *dosQObject = get_qobj_ptr(qobj);
-        // # Store the pointer to the nimQObject
-        *binded_ptr = get_binded_ptr(qobj);
-        // # Swap the vptr inside the nimQObject with the wrapper
-        set_qobj_ptr(qobj, wrapper);
-    }
-    forget(binded);
+        // Returning pointers to a wrapper and to an DosQObject, then swapping DosQObject with a fresh one
+        // Comments are copied 'as is' from the DOtherSide docs to ensure correctness
+        unsafe {
+            let mut qobj = &mut *shallow.get_qobj_from_ptr(binded);
+            // # Retrieve the DosQObject created dos_qobject_create() inside the nimQObject
+            *dosQObject = get_qobj_ptr(qobj);
+            // # Store the pointer to the nimQObject
+            *binded_ptr = get_binded_ptr(qobj);
+            // # Swap the vptr inside the nimQObject with the wrapper
+            set_qobj_ptr(qobj, wrapper);
+        }
+    })
 }
 
 struct UnsafeWrapper(UnsafeCell<HashMap<i32, Box<QMLRegisterable>>>);
@@ -75,7 +82,7 @@ fn register_with<T: QMLRegisterable + 'static>(t: T, r: Registerer) {
     let (major, minor, uri, qml) = t.qualify_to_register();
     let qmeta = QMetaDefinition::new(t.qmeta());
     let meta = QMeta::new_for_qobject(qmeta);
-    let mut map = unsafe { &mut *(REGISTERED_TYPES.0.get()) };
+    let map = unsafe { &mut *(REGISTERED_TYPES.0.get()) };
 
     let qrt = QmlRegisterType {
         major: major,
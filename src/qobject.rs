@@ -1,10 +1,14 @@
 use libc;
-use std::mem::forget;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 use std::slice::from_raw_parts_mut;
+use std::sync::{Condvar, Mutex};
 
 use qvariant::*;
 use types::*;
 use qmeta::*;
+use qpanic::*;
 
 #[doc(hidden)]
 /// Contains a pointer to raw Qt object.
@@ -13,6 +17,7 @@ pub struct QObject {
     ptr: DosQObject,
     qmeta: DosQMetaObject,
     binded_ptr: *mut libc::c_void,
+    id: usize,
 }
 
 extern "C" {
@@ -21,33 +26,80 @@ extern "C" {
                           metaObject: DosQMetaObject,
                           dObjectCallback: DObjectCallback)
                           -> DosQObject;
-    fn dos_qobject_signal_connect(senderVPtr: DosQObject,
-                                  signal: *const libc::c_char,
-                                  receiverVPtr: DosQObject,
-                                  method: *const libc::c_char,
-                                  qtype: i32)
-                                  -> bool;
     fn dos_qobject_delete(deleted: DosQObject);
+    fn dosext_qobject_throwError(vptr: DosQObject, message: DosCStr) -> bool;
     fn dos_qmetaobject_delete(vptr: DosQMetaObject);
 }
 
+/// Objects created by qml-rust and calls queued to them. Pointers are stored as addresses.
+#[derive(Default)]
+struct Registry {
+    // Maps `DosQObject`s to their entries
+    objects: HashMap<usize, RegistryEntry>,
+    // Maps data of queued calls, that are not delivered yet, to their `DosQObject`s, object ids and functions dropping the data
+    pending: HashMap<usize, (usize, usize, unsafe fn(usize))>,
+    // The id of the next created object
+    next_id: usize,
+}
+
+struct RegistryEntry {
+    binded_ptr: usize,
+    // Tells apart objects, that got the same address one after another
+    id: usize,
+    // Number of calls being posted to the object right now, it isn't destroyed until they are done
+    posting: usize,
+}
+
+impl Registry {
+    fn binded_ptr_of(&self, ptr: usize, id: Option<usize>) -> Option<*mut libc::c_void> {
+        match self.objects.get(&ptr) {
+            Some(entry) if id.map_or(true, |id| id == entry.id) => Some(entry.binded_ptr as *mut libc::c_void),
+            _ => None,
+        }
+    }
+}
+
+lazy_static!{
+    static ref QOBJECTS: Mutex<Registry> = Mutex::new(Registry::default());
+    // Notified, when an object is done with posting calls
+    static ref POSTED: Condvar = Condvar::new();
+}
+
 impl Drop for QObject {
     fn drop(&mut self) {
+        let undelivered: Vec<(usize, unsafe fn(usize))> = {
+            let ptr = self.ptr as usize;
+            let mut registry = QOBJECTS.lock().unwrap();
+            while registry.objects.get(&ptr).map_or(false, |entry| entry.posting > 0) {
+                registry = POSTED.wait(registry).unwrap();
+            }
+            registry.objects.remove(&ptr);
+            let undelivered: Vec<usize> = registry.pending
+                .iter()
+                .filter(|&(_, &(obj, _, _))| obj == ptr)
+                .map(|(&data, _)| data)
+                .collect();
+            undelivered.into_iter()
+                .map(|data| (data, registry.pending.remove(&data).unwrap().2))
+                .collect()
+        };
         unsafe {
             dos_qobject_delete(self.ptr);
             dos_qmetaobject_delete(self.qmeta);
+            // Qt discards events of a deleted object, so the calls won't be delivered.
+            // They are dropped without the lock, as they may own other objects
+            for (data, drop_data) in undelivered {
+                drop_data(data);
+            }
         }
     }
 }
 
-macro_rules! QT_connect {
-    ($sender:ident, $signal:ident, $receiver:ident, $method:tt) => {{
-        unimplemented!()
-    }}
-}
-
 /// This enum describes the types of connection that can be used between signals and slots.
 /// In particular, it determines whether a particular signal is delivered to a slot immediately or queued for delivery at a later time.
+///
+/// Qt::UniqueConnection is not a type of its own, it's combined with a type by [`unique`](#method.unique).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QtConnectionType {
     /// **(Default)** If the receiver lives in the thread that emits the signal, Qt::DirectConnection is used. Otherwise, Qt::QueuedConnection is used. The connection type is determined when the signal is emitted.
     Auto = 0,
@@ -57,8 +109,43 @@ pub enum QtConnectionType {
     Queued = 2,
     /// Same as Qt::QueuedConnection, except that the signalling thread blocks until the slot returns. This connection must not be used if the receiver lives in the signalling thread, or else the application will deadlock.
     BlockingQueued = 3,
-    /// This is a flag that can be combined with any one of the above connection types, using a bitwise OR. When Qt::UniqueConnection is set, QObject::connect() will fail if the connection already exists (i.e. if the same signal is already connected to the same slot for the same pair of objects). This flag was introduced in Qt 4.6.
-    Unique = 0x80,
+}
+
+impl QtConnectionType {
+    /// Sets Qt::UniqueConnection for this type. When Qt::UniqueConnection is set, QObject::connect() will fail if the connection already exists (i.e. if the same signal is already connected to the same slot for the same pair of objects).
+    pub fn unique(self) -> QtConnectionFlags {
+        QtConnectionFlags {
+            qtype: self,
+            unique: true,
+        }
+    }
+}
+
+/// A [`QtConnectionType`](enum.QtConnectionType.html), that may be combined with Qt::UniqueConnection.
+///
+/// Obtained from a type with `into()` or [`QtConnectionType::unique`](enum.QtConnectionType.html#method.unique).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QtConnectionFlags {
+    qtype: QtConnectionType,
+    unique: bool,
+}
+
+impl From<QtConnectionType> for QtConnectionFlags {
+    fn from(i: QtConnectionType) -> Self {
+        QtConnectionFlags {
+            qtype: i,
+            unique: false,
+        }
+    }
+}
+
+/// Returns the value of `Qt::ConnectionType` for these flags.
+pub fn connection_flags_bits(flags: QtConnectionFlags) -> i32 {
+    if flags.unique {
+        flags.qtype as i32 | 0x80
+    } else {
+        flags.qtype as i32
+    }
 }
 
 /// Called when a slot should be executed
@@ -77,12 +164,132 @@ impl QObject {
             // println!("Adress of wrapper {:p}", obj);
             let obj = Box::new(obj);
             let binded_ptr = Box::into_raw(obj) as *mut libc::c_void;
+            let ptr = dos_qobject_create(binded_ptr, get_dos_qmeta(&meta), callback);
+            let id = register(ptr as usize, binded_ptr as usize);
             QObject {
-                ptr: dos_qobject_create(binded_ptr, get_dos_qmeta(&meta), callback),
+                ptr: ptr,
                 qmeta: get_dos_qmeta(&meta),
                 binded_ptr: binded_ptr,
+                id: id,
+            }
+        }
+    }
+}
+
+/// A `QObject` stored in a [`QVariant`](struct.QVariant.html), obtained with
+/// [`QVariant::to_qobject`](struct.QVariant.html#method.to_qobject).
+///
+/// It does not own the object, and may point to an object created either by qml-rust or by QML itself.
+#[derive(Debug)]
+pub struct QObjectRef<'a> {
+    ptr: DosQObject,
+    phantom: PhantomData<&'a QVariant>,
+}
+
+impl<'a> QObjectRef<'a> {
+    /// Returns a reference to the Rust wrapper, generated by [`Q_OBJECT`](macro.Q_OBJECT!.html),
+    /// if this object was created by qml-rust and is of type `T`.
+    ///
+    /// # Safety
+    ///
+    /// The wrapper is looked up in the registry of live objects, but neither Rust nor Qt
+    /// keep it borrowed or alive afterwards. The caller must make sure, that:
+    ///
+    /// * the object is not borrowed mutably while the reference is used, for example
+    ///   it is not the object, whose slot is running and received this `QObjectRef`;
+    /// * the object is not destroyed while the reference is used.
+    pub unsafe fn downcast_ref<T: QObjectMacro + 'static>(&self) -> Option<&T> {
+        let binded_ptr = match binded_ptr_of(self.ptr, None) {
+            Some(binded_ptr) => binded_ptr as *const &mut QObjectMacro,
+            None => return None,
+        };
+        (*binded_ptr).as_any().downcast_ref::<T>()
+    }
+}
+
+pub fn new_qobject_ref<'a>(ptr: DosQObject) -> QObjectRef<'a> {
+    QObjectRef {
+        ptr: ptr,
+        phantom: PhantomData,
+    }
+}
+
+pub fn get_qobject_ref_ptr(o: &QObjectRef) -> DosQObject {
+    o.ptr
+}
+
+/// Adds an object to the registry, returning its id.
+fn register(ptr: usize, binded_ptr: usize) -> usize {
+    let mut registry = QOBJECTS.lock().unwrap();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.objects.insert(ptr,
+                            RegistryEntry {
+                                binded_ptr: binded_ptr,
+                                id: id,
+                                posting: 0,
+                            });
+    id
+}
+
+/// Returns the `binded_ptr` of an object, if it was created by qml-rust and still exists.
+///
+/// If `id` is given, it must be the id of the object, as a new object may get the address of a destroyed one.
+pub fn binded_ptr_of(ptr: DosQObject, id: Option<usize>) -> Option<*mut libc::c_void> {
+    QOBJECTS.lock().unwrap().binded_ptr_of(ptr as usize, id)
+}
+
+/// Queues a call to the object with this address and id with `post`, if the object still exists.
+/// The object isn't destroyed, until `post` returns.
+///
+/// `data` is owned by the registry from now on. It's dropped with `drop_data`, if the call wasn't queued,
+/// which is reported by returning `false`, or if the object is destroyed before the call is taken with `take_queued_call`.
+pub fn queue_call<F: FnOnce() -> bool>(ptr: DosQObject,
+                                       id: usize,
+                                       data: usize,
+                                       drop_data: unsafe fn(usize),
+                                       post: F)
+                                       -> bool {
+    let ptr = ptr as usize;
+    let reserved = {
+        let mut registry = QOBJECTS.lock().unwrap();
+        let reserved = match registry.objects.get_mut(&ptr) {
+            Some(entry) if entry.id == id => {
+                entry.posting += 1;
+                true
             }
+            _ => false,
+        };
+        if reserved {
+            registry.pending.insert(data, (ptr, id, drop_data));
         }
+        reserved
+    };
+    if !reserved {
+        unsafe { drop_data(data) };
+        return false;
+    }
+    // Posting is done without the lock, so other objects can be used meanwhile
+    let posted = post();
+    let rolled_back = {
+        let mut registry = QOBJECTS.lock().unwrap();
+        registry.objects.get_mut(&ptr).unwrap().posting -= 1;
+        POSTED.notify_all();
+        if posted { None } else { registry.pending.remove(&data) }
+    };
+    if let Some((_, _, drop_data)) = rolled_back {
+        unsafe { drop_data(data) };
+    }
+    posted
+}
+
+/// Takes back `data` of a delivered call, returning the `binded_ptr` of its object,
+/// or `None` if the object was destroyed and `data` is already dropped.
+pub fn take_queued_call(data: usize) -> Option<*mut libc::c_void> {
+    let mut registry = QOBJECTS.lock().unwrap();
+    match registry.pending.remove(&data) {
+        Some((ptr, id, _)) => registry.binded_ptr_of(ptr, Some(id)),
+        None => None,
     }
 }
 
@@ -90,31 +297,135 @@ pub fn get_qobj_ptr(o: &QObject) -> DosQObject {
     o.ptr
 }
 
+pub fn get_qobj_id(o: &QObject) -> usize {
+    o.id
+}
+
 pub fn get_binded_ptr(o: &QObject) -> *mut libc::c_void {
     o.binded_ptr
 }
 
 pub fn set_qobj_ptr(o: &mut QObject, ptr: DosQObject) {
+    let mut registry = QOBJECTS.lock().unwrap();
+    if let Some(entry) = registry.objects.remove(&(o.ptr as usize)) {
+        registry.objects.insert(ptr as usize, entry);
+    }
+    for pending in registry.pending.values_mut().filter(|pending| pending.0 == o.ptr as usize) {
+        pending.0 = ptr as usize;
+    }
     o.ptr = ptr;
 }
 
+/// Throws an error of a slot into JavaScript with `throw_error`, which fails if the slot wasn't called from JavaScript,
+/// e.g. by a signal, a queued call or C++. Then returns a line to log instead.
+fn report_slot_error<F: FnOnce(&CStr) -> bool>(slot: &str, message: &str, throw_error: F) -> Option<String> {
+    let cmessage = CString::new(message.replace('\0', "")).unwrap();
+    if throw_error(&cmessage) {
+        None
+    } else {
+        Some(format!("Slot `{}` failed outside of JavaScript: {}", slot, message))
+    }
+}
+
 extern "C" fn callback(obj: *mut libc::c_void,
                        slotName: DosQVariant,
                        argc: i32,
                        argv: *mut DosQVariant) {
     unsafe {
-        let mut obj: Box<&mut QObjectMacro> = Box::from_raw(obj as *mut &mut QObjectMacro);
-        // println!("Calling adress of wrapper  {:p}", *obj.as_mut());
+        let obj = obj as *mut &mut QObjectMacro;
+        // println!("Calling adress of wrapper  {:p}", *obj);
         let slice = from_raw_parts_mut(argv, argc as usize);
-        let vec: Vec<QVariant> = slice.iter().skip(1).map(|&dq| dq.into()).collect();
-        let slotName: String = new_qvariant(slotName).into();
+        let slotName = qvariant_to_string(&new_qvariant_ref(slotName));
         // println!("Right before going in... name: {}, argc: {}",
         //  slotName,
         //  argc);
-        if let Some(qvar) = obj.qslot_call(&slotName, vec) {
-            let mut qv: QVariant = slice[0].into();
-            qv.set(qvar);
-        }
-        forget(obj);
+        let location = || format!("slot `{}` of `{}`", slotName, (*obj).qmeta().3);
+        catch_panic(location, || {
+            let vec: Vec<QVariantRef> = slice.iter().skip(1).map(|&dq| new_qvariant_ref(dq)).collect();
+            match (*obj).qslot_call(&slotName, vec) {
+                Ok(Some(qvar)) => assign_qvariant(slice[0] as MutDosQVariant, &qvar),
+                Ok(None) => {}
+                Err(message) => {
+                    let qobj = get_qobj_ptr((*obj).get_qobj());
+                    let thrown = |message: &CStr| dosext_qobject_throwError(qobj, message.as_ptr());
+                    if let Some(log) = report_slot_error(&slotName, &message, thrown) {
+                        eprintln!("{}", log);
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    lazy_static!{
+        static ref DROPPED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+    }
+
+    unsafe fn drop_data(data: usize) {
+        DROPPED.lock().unwrap().push(data);
+    }
+
+    fn dropped(data: usize) -> bool {
+        DROPPED.lock().unwrap().contains(&data)
+    }
+
+    #[test]
+    fn calls_to_an_object_at_a_reused_address_are_rejected() {
+        let ptr = 0x1000;
+        let old = register(ptr, 1);
+        QOBJECTS.lock().unwrap().objects.remove(&ptr);
+        let new = register(ptr, 2);
+        assert!(old != new);
+        assert_eq!(binded_ptr_of(ptr as DosQObject, Some(old)), None);
+        assert_eq!(binded_ptr_of(ptr as DosQObject, Some(new)), Some(2 as *mut libc::c_void));
+        assert_eq!(binded_ptr_of(ptr as DosQObject, None), Some(2 as *mut libc::c_void));
+        assert!(!queue_call(ptr as DosQObject, old, 0x1001, drop_data, || true));
+        assert!(dropped(0x1001));
+    }
+
+    #[test]
+    fn delivered_calls_are_taken_once() {
+        let ptr = 0x2000;
+        let id = register(ptr, 3);
+        // The registry isn't locked while posting
+        assert!(queue_call(ptr as DosQObject, id, 0x2001, drop_data, || {
+            binded_ptr_of(ptr as DosQObject, Some(id)).is_some()
+        }));
+        assert_eq!(take_queued_call(0x2001), Some(3 as *mut libc::c_void));
+        assert_eq!(take_queued_call(0x2001), None);
+        assert!(!dropped(0x2001));
+    }
+
+    #[test]
+    fn calls_that_failed_to_post_are_rolled_back() {
+        let ptr = 0x3000;
+        let id = register(ptr, 4);
+        assert!(!queue_call(ptr as DosQObject, id, 0x3001, drop_data, || false));
+        assert!(dropped(0x3001));
+        assert_eq!(take_queued_call(0x3001), None);
+        assert_eq!(QOBJECTS.lock().unwrap().objects[&ptr].posting, 0);
+    }
+
+    #[test]
+    fn slot_errors_outside_of_javascript_are_logged() {
+        // A slot called from Rust, e.g. through `connect`, has no JavaScript function to throw into
+        let log = report_slot_error("increment", "overflow", |message| {
+            assert_eq!(message.to_str(), Ok("overflow"));
+            false
+        });
+        assert_eq!(log, Some("Slot `increment` failed outside of JavaScript: overflow".to_string()));
+    }
+
+    #[test]
+    fn slot_errors_thrown_into_javascript_are_not_logged() {
+        let log = report_slot_error("parse", "bad\0 input", |message| {
+            assert_eq!(message.to_str(), Ok("bad input"));
+            true
+        });
+        assert_eq!(log, None);
     }
 }
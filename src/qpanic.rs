@@ -0,0 +1,147 @@
+//! Keeps panics of Rust code, called by Qt, from unwinding into C++.
+//!
+//! Every `extern "C"` callback runs its body in `catch_panic`, which decides what to do
+//! with a caught panic according to the current `PanicPolicy`.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::RwLock;
+
+/// What happens when Rust code, called by Qt (a slot, a model or a registered type), panics.
+///
+/// The policy is global for the process and is set with
+/// [`QmlEngine::set_panic_policy`](struct.QmlEngine.html#method.set_panic_policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// **(Default)** Logs the panic and aborts the process.
+    Abort,
+    /// Logs the panic and returns to Qt as if the callback did nothing.
+    LogAndContinue,
+    /// Passes the panic to a hook, set with
+    /// [`QmlEngine::set_panic_hook`](struct.QmlEngine.html#method.set_panic_hook), and continues.
+    /// Until a hook is set, behaves like `LogAndContinue`.
+    Hook,
+}
+
+/// Describes a panic caught at the boundary between Qt and Rust.
+#[derive(Debug, Clone)]
+pub struct CallbackPanic {
+    /// Where the panic happened, e.g. "slot `click` of `Test`"
+    pub location: String,
+    /// Message of the panic, if it was a string
+    pub message: String,
+}
+
+type PanicHook = Box<Fn(&CallbackPanic) + Send + Sync>;
+
+lazy_static!{
+    static ref PANIC_HANDLING: RwLock<(PanicPolicy, Option<PanicHook>)> = RwLock::new((PanicPolicy::Abort, None));
+}
+
+pub fn set_panic_policy(policy: PanicPolicy) {
+    PANIC_HANDLING.write().unwrap().0 = policy;
+}
+
+pub fn set_panic_hook(hook: PanicHook) {
+    *PANIC_HANDLING.write().unwrap() = (PanicPolicy::Hook, Some(hook));
+}
+
+fn panic_message(payload: &Box<Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<Any>".to_string()
+    }
+}
+
+/// Runs `f`, returning `None` if it panicked and the policy allows to continue.
+///
+/// `location` is called only when there was a panic, so it may format freely.
+pub fn catch_panic<R, F, L>(location: L, f: F) -> Option<R>
+    where F: FnOnce() -> R,
+          L: FnOnce() -> String
+{
+    let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => return Some(res),
+        Err(payload) => payload,
+    };
+    let info = CallbackPanic {
+        location: location(),
+        message: panic_message(&payload),
+    };
+    let handling = match PANIC_HANDLING.read() {
+        Ok(handling) => handling,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    match *handling {
+        (PanicPolicy::Hook, Some(ref hook)) => {
+            if panic::catch_unwind(AssertUnwindSafe(|| hook(&info))).is_err() {
+                eprintln!("Panic hook panicked while handling a panic in {}", info.location);
+                process::abort();
+            }
+        }
+        (PanicPolicy::Abort, _) => {
+            eprintln!("Panic in {}: {}, aborting", info.location, info.message);
+            process::abort();
+        }
+        (PanicPolicy::Hook, None) |
+        (PanicPolicy::LogAndContinue, _) => eprintln!("Panic in {}: {}", info.location, info.message),
+    }
+    None
+}
+
+/// Same as `catch_panic`, but aborts in any case,
+/// for callbacks that can't return to Qt without a result.
+pub fn catch_panic_or_abort<R, F, L>(location: L, f: F) -> R
+    where F: FnOnce() -> R,
+          L: FnOnce() -> String
+{
+    match catch_panic(location, f) {
+        Some(res) => res,
+        None => {
+            eprintln!("Can't continue without a result, aborting");
+            process::abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn panic_messages_are_extracted() {
+        assert_eq!(panic_message(&(Box::new("static") as Box<Any + Send>)), "static");
+        assert_eq!(panic_message(&(Box::new("owned".to_string()) as Box<Any + Send>)), "owned");
+        assert_eq!(panic_message(&(Box::new(42) as Box<Any + Send>)), "Box<Any>");
+    }
+
+    // The policy is global, so all policies, except for aborting, are checked in one test
+    #[test]
+    fn panics_are_handled_according_to_policy() {
+        let location = || "slot `click` of `Test`".to_string();
+        assert_eq!(catch_panic(|| unreachable!(), || 42), Some(42));
+
+        set_panic_policy(PanicPolicy::LogAndContinue);
+        assert_eq!(catch_panic(location, || -> i32 { panic!("boom") }), None);
+
+        // Without a hook, `Hook` falls back to logging
+        set_panic_policy(PanicPolicy::Hook);
+        assert_eq!(catch_panic(location, || -> i32 { panic!("boom") }), None);
+
+        let caught = Arc::new(Mutex::new(Vec::new()));
+        let hook_caught = caught.clone();
+        set_panic_hook(Box::new(move |info: &CallbackPanic| {
+            hook_caught.lock().unwrap().push((info.location.clone(), info.message.clone()));
+        }));
+        assert_eq!(catch_panic(location, || -> i32 { panic!("at {}", 42) }), None);
+        assert_eq!(*caught.lock().unwrap(),
+                   vec![("slot `click` of `Test`".to_string(), "at 42".to_string())]);
+
+        *PANIC_HANDLING.write().unwrap() = (PanicPolicy::Abort, None);
+    }
+}
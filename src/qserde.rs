@@ -0,0 +1,620 @@
+//! Conversion of `serde` types to and from [`QVariant`](struct.QVariant.html).
+//!
+//! Structs and maps become `QVariantMap` (objects in QML), sequences and tuples become `QVariantList` (arrays),
+//! scalars become corresponding scalar `QVariant`s. Enabled with a `serde` feature.
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::vec::IntoIter;
+
+use serde::{de, ser, Serialize};
+use serde::de::{DeserializeOwned, Visitor};
+use serde::de::value::{SeqDeserializer, StringDeserializer};
+
+use qvariant::*;
+
+/// Converts any serializable value into a `QVariant`.
+///
+/// # Examples
+/// ```
+/// # extern crate qml;
+/// # use qml::*;
+/// # use std::collections::HashMap;
+/// # fn main() {
+/// let mut scores = HashMap::new();
+/// scores.insert("John", vec![2, 2]);
+/// scores.insert("Mary", vec![0, 1]);
+/// let qvar: QVariant = to_qvariant(&scores).unwrap();
+/// # }
+/// ```
+pub fn to_qvariant<T: Serialize + ?Sized>(value: &T) -> Result<QVariant, SerdeError> {
+    value.serialize(QVariantSerializer)
+}
+
+/// Converts a `QVariant` into any deserializable value.
+///
+/// Numbers coming from QML are doubles, so any integral double is accepted for an integer.
+pub fn from_qvariant<T: DeserializeOwned>(qvar: &QVariant) -> Result<T, SerdeError> {
+    T::deserialize(QVariantDeserializer(qvar))
+}
+
+/// An error occured during (de)serialization of a `QVariant`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerdeError(String);
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for SerdeError {}
+
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl From<QVariantConversionError> for SerdeError {
+    fn from(i: QVariantConversionError) -> Self {
+        SerdeError(i.to_string())
+    }
+}
+
+// Integers are kept as `int` while they fit, otherwise they become 64-bit integers.
+fn integer(v: i64) -> QVariant {
+    if v >= i32::min_value() as i64 && v <= i32::max_value() as i64 {
+        QVariant::from(v as i32)
+    } else {
+        QVariant::from(v)
+    }
+}
+
+fn single_entry(key: &str, value: QVariant) -> QVariant {
+    let mut map = HashMap::new();
+    map.insert(key.to_string(), value);
+    map.into()
+}
+
+struct QVariantSerializer;
+
+impl ser::Serializer for QVariantSerializer {
+    type Ok = QVariant;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<QVariant, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<QVariant, SerdeError> {
+        Ok(integer(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<QVariant, SerdeError> {
+        Ok(integer(v as i64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<QVariant, SerdeError> {
+        if v <= i64::max_value() as u64 {
+            Ok(integer(v as i64))
+        } else {
+            Ok(QVariant::from(v))
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<QVariant, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<QVariant, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<QVariant, SerdeError> {
+        Ok(v.to_string().into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<QVariant, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<QVariant, SerdeError> {
+        Ok(v.into())
+    }
+
+    fn serialize_none(self) -> Result<QVariant, SerdeError> {
+        Ok(QVariant::null())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<QVariant, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<QVariant, SerdeError> {
+        Ok(QVariant::default())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<QVariant, SerdeError> {
+        Ok(QVariant::default())
+    }
+
+    fn serialize_unit_variant(self,
+                              name: &'static str,
+                              index: u32,
+                              variant: &'static str)
+                              -> Result<QVariant, SerdeError> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self,
+                                                       name: &'static str,
+                                                       value: &T)
+                                                       -> Result<QVariant, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self,
+                                                        name: &'static str,
+                                                        index: u32,
+                                                        variant: &'static str,
+                                                        value: &T)
+                                                        -> Result<QVariant, SerdeError> {
+        Ok(single_entry(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, SerdeError> {
+        Ok(SerializeList {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self,
+                              name: &'static str,
+                              len: usize)
+                              -> Result<SerializeList, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self,
+                               name: &'static str,
+                               index: u32,
+                               variant: &'static str,
+                               len: usize)
+                               -> Result<SerializeList, SerdeError> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            variant: None,
+            entries: HashMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeMap, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self,
+                                name: &'static str,
+                                index: u32,
+                                variant: &'static str,
+                                len: usize)
+                                -> Result<SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            variant: Some(variant),
+            entries: HashMap::new(),
+            key: None,
+        })
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    items: Vec<QVariant>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.items.push(value.serialize(QVariantSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<QVariant, SerdeError> {
+        let list = QVariant::from(self.items);
+        match self.variant {
+            Some(variant) => Ok(single_entry(variant, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+struct SerializeMap {
+    variant: Option<&'static str>,
+    entries: HashMap<String, QVariant>,
+    key: Option<String>,
+}
+
+impl SerializeMap {
+    fn finish(self) -> Result<QVariant, SerdeError> {
+        let map = QVariant::from(self.entries);
+        match self.variant {
+            Some(variant) => Ok(single_entry(variant, map)),
+            None => Ok(map),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        // Keys of `QVariantMap` are strings, so keys that are not strings are written down as strings
+        let key = key.serialize(QVariantSerializer)?;
+        match key.value() {
+            QVariantValue::String(s) => self.key = Some(s),
+            QVariantValue::Int(_) |
+            QVariantValue::UInt(_) |
+            QVariantValue::LongLong(_) |
+            QVariantValue::ULongLong(_) |
+            QVariantValue::Float(_) |
+            QVariantValue::Double(_) |
+            QVariantValue::Bool(_) => self.key = Some(qvariant_to_string(&key)),
+            _ => return Err(SerdeError(format!("a key of type `{}` can't be used in a QVariantMap",
+                                               key.type_name()))),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self.key.take().expect("serialize_value is called before serialize_key");
+        self.entries.insert(key, value.serialize(QVariantSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self,
+                                              key: &'static str,
+                                              value: &T)
+                                              -> Result<(), SerdeError> {
+        self.entries.insert(key.to_string(), value.serialize(QVariantSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = QVariant;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self,
+                                              key: &'static str,
+                                              value: &T)
+                                              -> Result<(), SerdeError> {
+        self.entries.insert(key.to_string(), value.serialize(QVariantSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<QVariant, SerdeError> {
+        self.finish()
+    }
+}
+
+struct QVariantDeserializer<'a>(&'a QVariant);
+
+impl<'a> QVariantDeserializer<'a> {
+    fn integer(&self) -> Result<i128, SerdeError> {
+        qvariant_to_integer(self.0).ok_or_else(|| self.unexpected("an integer"))
+    }
+
+    fn unexpected(&self, expected: &str) -> SerdeError {
+        SerdeError(format!("expected {}, found a QVariant of type `{}`",
+                           expected,
+                           self.0.type_name()))
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident: $t:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+                let v = self.integer()?;
+                match <$t>::try_from(v) {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(SerdeError(format!("{} is out of range for `{}`", v, stringify!($t)))),
+                }
+            }
+        )*
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for QVariantDeserializer<'a> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0.value() {
+            QVariantValue::Invalid | QVariantValue::Null => visitor.visit_unit(),
+            QVariantValue::Bool(v) => visitor.visit_bool(v),
+            QVariantValue::Int(v) => visitor.visit_i32(v),
+            QVariantValue::UInt(v) => visitor.visit_u32(v),
+            QVariantValue::LongLong(v) => visitor.visit_i64(v),
+            QVariantValue::ULongLong(v) => visitor.visit_u64(v),
+            QVariantValue::Float(v) => visitor.visit_f32(v),
+            QVariantValue::Double(v) => visitor.visit_f64(v),
+            QVariantValue::String(v) => visitor.visit_string(v),
+            QVariantValue::ByteArray(v) => visitor.visit_byte_buf(v),
+            QVariantValue::List(v) => visitor.visit_seq(ListAccess(v.into_iter())),
+            QVariantValue::Map(v) => {
+                visitor.visit_map(MapAccess {
+                    entries: v.into_iter(),
+                    value: None,
+                })
+            }
+            _ => Err(self.unexpected("a value representable in Rust")),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u64 => visit_u64: u64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0.value() {
+            QVariantValue::Invalid | QVariantValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self,
+                                                   name: &'static str,
+                                                   visitor: V)
+                                                   -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self,
+                                         name: &'static str,
+                                         variants: &'static [&'static str],
+                                         visitor: V)
+                                         -> Result<V::Value, SerdeError> {
+        match self.0.value() {
+            QVariantValue::String(variant) => visitor.visit_enum(StringDeserializer::<SerdeError>::new(variant)),
+            QVariantValue::Map(map) => {
+                let mut entries = map.into_iter();
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => {
+                        visitor.visit_enum(EnumAccess {
+                            variant: variant,
+                            value: value,
+                        })
+                    }
+                    _ => Err(SerdeError("expected a map with a single key for an enum".to_string())),
+                }
+            }
+            _ => Err(self.unexpected("a string or a map for an enum")),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0.value() {
+            // `Vec<u8>` asks for a sequence, unless `serde_bytes` is used
+            QVariantValue::ByteArray(v) => visitor.visit_seq(SeqDeserializer::<_, SerdeError>::new(v.into_iter())),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf unit unit_struct tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ListAccess(IntoIter<QVariant>);
+
+impl<'de> de::SeqAccess<'de> for ListAccess {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self,
+                                                      seed: T)
+                                                      -> Result<Option<T::Value>, SerdeError> {
+        match self.0.next() {
+            Some(qvar) => seed.deserialize(QVariantDeserializer(&qvar)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapAccess {
+    entries: ::std::collections::hash_map::IntoIter<String, QVariant>,
+    value: Option<QVariant>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self,
+                                                  seed: K)
+                                                  -> Result<Option<K::Value>, SerdeError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(StringDeserializer::<SerdeError>::new(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self,
+                                                    seed: V)
+                                                    -> Result<V::Value, SerdeError> {
+        let value = self.value.take().expect("next_value_seed is called before next_key_seed");
+        seed.deserialize(QVariantDeserializer(&value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: QVariant,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = SerdeError;
+    type Variant = VariantAccess;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self,
+                                                 seed: V)
+                                                 -> Result<(V::Value, VariantAccess), SerdeError> {
+        let variant = seed.deserialize(StringDeserializer::<SerdeError>::new(self.variant))?;
+        Ok((variant, VariantAccess(self.value)))
+    }
+}
+
+struct VariantAccess(QVariant);
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self,
+                                                         seed: T)
+                                                         -> Result<T::Value, SerdeError> {
+        seed.deserialize(QVariantDeserializer(&self.0))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(QVariantDeserializer(&self.0), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self,
+                                       fields: &'static [&'static str],
+                                       visitor: V)
+                                       -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(QVariantDeserializer(&self.0), visitor)
+    }
+}
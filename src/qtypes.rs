@@ -3,12 +3,58 @@
 //! Provides definition of [`QMetaType`](enum.QMetaType.html) enum, that contains mapping of named types
 //! and a trait [`QMetaTypable`](trait.QMetaTypable.html), that controls which types are able to be used in signals, slots or properties.
 
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use qvariant::{self, QVariant, QVariantConversionError};
+
 /// Provides an associated variant of enum for a type.
 ///
 /// Only types that implement this, may be used as types in signals, slots or properties.
 pub trait QMetaTypable {
     /// Returns an associate variant of QMetaType
     fn metatype() -> QMetaType;
+
+    // The methods below decide how a `Vec` of this type is passed to QML. Only `u8` overrides them,
+    // so that `Vec<u8>` becomes a `QByteArray` instead of a list of numbers.
+
+    #[doc(hidden)]
+    fn list_metatype() -> QMetaType {
+        QMetaType::QVariantList
+    }
+
+    #[doc(hidden)]
+    fn list_into_qvariant(list: Vec<Self>) -> QVariant
+        where Self: Sized + Into<QVariant>
+    {
+        qvariant::from_vec(list)
+    }
+
+    #[doc(hidden)]
+    fn list_from_qvariant(i: &QVariant) -> Result<Vec<Self>, QVariantConversionError>
+        where Self: Sized + TryFrom<QVariant>,
+              QVariantConversionError: From<Self::Error>
+    {
+        qvariant::vec_from_qvariant(i)
+    }
+}
+
+impl QMetaTypable for i8 {
+    fn metatype() -> QMetaType {
+        QMetaType::SChar
+    }
+}
+
+impl QMetaTypable for i16 {
+    fn metatype() -> QMetaType {
+        QMetaType::Short
+    }
+}
+
+impl QMetaTypable for u16 {
+    fn metatype() -> QMetaType {
+        QMetaType::UShort
+    }
 }
 
 impl QMetaTypable for i32 {
@@ -17,20 +63,341 @@ impl QMetaTypable for i32 {
     }
 }
 
+impl QMetaTypable for u32 {
+    fn metatype() -> QMetaType {
+        QMetaType::UInt
+    }
+}
+
+impl QMetaTypable for i64 {
+    fn metatype() -> QMetaType {
+        QMetaType::LongLong
+    }
+}
+
+impl QMetaTypable for u64 {
+    fn metatype() -> QMetaType {
+        QMetaType::ULongLong
+    }
+}
+
+/// `usize` is always passed as a 64-bit unsigned integer.
+impl QMetaTypable for usize {
+    fn metatype() -> QMetaType {
+        QMetaType::ULongLong
+    }
+}
+
+/// `isize` is always passed as a 64-bit signed integer.
+impl QMetaTypable for isize {
+    fn metatype() -> QMetaType {
+        QMetaType::LongLong
+    }
+}
+
+/// A `char` is passed to QML as a one-character `QString`, as `QChar` can't hold every Unicode scalar value.
+impl QMetaTypable for char {
+    fn metatype() -> QMetaType {
+        QMetaType::QString
+    }
+}
+
 impl QMetaTypable for String {
     fn metatype() -> QMetaType {
         QMetaType::QString
     }
 }
 
+impl<'a> QMetaTypable for &'a str {
+    fn metatype() -> QMetaType {
+        QMetaType::QString
+    }
+}
+
+impl QMetaTypable for bool {
+    fn metatype() -> QMetaType {
+        QMetaType::Bool
+    }
+}
+
+impl QMetaTypable for f32 {
+    fn metatype() -> QMetaType {
+        QMetaType::Float
+    }
+}
+
+impl QMetaTypable for f64 {
+    fn metatype() -> QMetaType {
+        QMetaType::Double
+    }
+}
+
+/// `QVariant` is seen as a `var` in QML, so it can hold a value of any type.
+impl QMetaTypable for QVariant {
+    fn metatype() -> QMetaType {
+        QMetaType::QVariant
+    }
+}
+
+/// Optional values are passed to QML as `QVariant`, so `None` can become `null`.
+impl<T: QMetaTypable> QMetaTypable for Option<T> {
+    fn metatype() -> QMetaType {
+        QMetaType::QVariant
+    }
+}
+
+/// A slot returning `Result` returns the `Ok` value to QML, and throws an `Error` otherwise.
+impl<T: QMetaTypable, E> QMetaTypable for Result<T, E> {
+    fn metatype() -> QMetaType {
+        T::metatype()
+    }
+}
+
+/// Makes sense only as a return type of a slot, e.g. `Result<(), E>`.
+impl QMetaTypable for () {
+    fn metatype() -> QMetaType {
+        QMetaType::Void
+    }
+}
+
+/// Lists are passed to QML as `QVariantList`, i.e. arrays in JavaScript.
+///
+/// Bytes are the exception: `Vec<u8>` is passed as `QByteArray`, i.e. an `ArrayBuffer` in JavaScript.
+impl<T: QMetaTypable> QMetaTypable for Vec<T> {
+    fn metatype() -> QMetaType {
+        T::list_metatype()
+    }
+}
+
+/// Maps are passed to QML as `QVariantMap`, i.e. plain objects in JavaScript.
+impl<T: QMetaTypable> QMetaTypable for HashMap<String, T> {
+    fn metatype() -> QMetaType {
+        QMetaType::QVariantMap
+    }
+}
+
+impl<T: QMetaTypable> QMetaTypable for BTreeMap<String, T> {
+    fn metatype() -> QMetaType {
+        QMetaType::QVariantMap
+    }
+}
+
 /// Analogue of [`Qt::QMetaType::Type`](http://doc.qt.io/qt-5/qmetatype.html#Type-enum)
 ///
 /// `QMetaType` in Qt manages named types in the meta-object system.
+/// Types with ids starting from `User` are registered at runtime and don't have a variant here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QMetaType {
-    Void = 43,
+    UnknownType = 0,
     Bool = 1,
     Int = 2,
+    UInt = 3,
+    LongLong = 4,
+    ULongLong = 5,
     Double = 6,
-    Long = 32,
+    QChar = 7,
+    QVariantMap = 8,
+    QVariantList = 9,
     QString = 10,
+    QStringList = 11,
+    QByteArray = 12,
+    QBitArray = 13,
+    QDate = 14,
+    QTime = 15,
+    QDateTime = 16,
+    QUrl = 17,
+    QLocale = 18,
+    QRect = 19,
+    QRectF = 20,
+    QSize = 21,
+    QSizeF = 22,
+    QLine = 23,
+    QLineF = 24,
+    QPoint = 25,
+    QPointF = 26,
+    QRegExp = 27,
+    QVariantHash = 28,
+    QEasingCurve = 29,
+    QUuid = 30,
+    VoidStar = 31,
+    Long = 32,
+    Short = 33,
+    Char = 34,
+    ULong = 35,
+    UShort = 36,
+    UChar = 37,
+    Float = 38,
+    QObjectStar = 39,
+    SChar = 40,
+    QVariant = 41,
+    QModelIndex = 42,
+    Void = 43,
+    QRegularExpression = 44,
+    QJsonValue = 45,
+    QJsonObject = 46,
+    QJsonArray = 47,
+    QJsonDocument = 48,
+    QByteArrayList = 49,
+    QPersistentModelIndex = 50,
+    Nullptr = 51,
+    QCborSimpleType = 52,
+    QCborValue = 53,
+    QCborArray = 54,
+    QCborMap = 55,
+    QFont = 64,
+    QPixmap = 65,
+    QBrush = 66,
+    QColor = 67,
+    QPalette = 68,
+    QIcon = 69,
+    QImage = 70,
+    QPolygon = 71,
+    QRegion = 72,
+    QBitmap = 73,
+    QCursor = 74,
+    QKeySequence = 75,
+    QPen = 76,
+    QTextLength = 77,
+    QTextFormat = 78,
+    QMatrix = 79,
+    QTransform = 80,
+    QMatrix4x4 = 81,
+    QVector2D = 82,
+    QVector3D = 83,
+    QVector4D = 84,
+    QQuaternion = 85,
+    QPolygonF = 86,
+    QSizePolicy = 121,
+    User = 1024,
+}
+
+impl QMetaType {
+    /// Returns a variant for a Qt type id, or `None` for ids of user types and unknown ids.
+    pub fn from_id(id: i32) -> Option<QMetaType> {
+        match id {
+            0 => Some(QMetaType::UnknownType),
+            1 => Some(QMetaType::Bool),
+            2 => Some(QMetaType::Int),
+            3 => Some(QMetaType::UInt),
+            4 => Some(QMetaType::LongLong),
+            5 => Some(QMetaType::ULongLong),
+            6 => Some(QMetaType::Double),
+            7 => Some(QMetaType::QChar),
+            8 => Some(QMetaType::QVariantMap),
+            9 => Some(QMetaType::QVariantList),
+            10 => Some(QMetaType::QString),
+            11 => Some(QMetaType::QStringList),
+            12 => Some(QMetaType::QByteArray),
+            13 => Some(QMetaType::QBitArray),
+            14 => Some(QMetaType::QDate),
+            15 => Some(QMetaType::QTime),
+            16 => Some(QMetaType::QDateTime),
+            17 => Some(QMetaType::QUrl),
+            18 => Some(QMetaType::QLocale),
+            19 => Some(QMetaType::QRect),
+            20 => Some(QMetaType::QRectF),
+            21 => Some(QMetaType::QSize),
+            22 => Some(QMetaType::QSizeF),
+            23 => Some(QMetaType::QLine),
+            24 => Some(QMetaType::QLineF),
+            25 => Some(QMetaType::QPoint),
+            26 => Some(QMetaType::QPointF),
+            27 => Some(QMetaType::QRegExp),
+            28 => Some(QMetaType::QVariantHash),
+            29 => Some(QMetaType::QEasingCurve),
+            30 => Some(QMetaType::QUuid),
+            31 => Some(QMetaType::VoidStar),
+            32 => Some(QMetaType::Long),
+            33 => Some(QMetaType::Short),
+            34 => Some(QMetaType::Char),
+            35 => Some(QMetaType::ULong),
+            36 => Some(QMetaType::UShort),
+            37 => Some(QMetaType::UChar),
+            38 => Some(QMetaType::Float),
+            39 => Some(QMetaType::QObjectStar),
+            40 => Some(QMetaType::SChar),
+            41 => Some(QMetaType::QVariant),
+            42 => Some(QMetaType::QModelIndex),
+            43 => Some(QMetaType::Void),
+            44 => Some(QMetaType::QRegularExpression),
+            45 => Some(QMetaType::QJsonValue),
+            46 => Some(QMetaType::QJsonObject),
+            47 => Some(QMetaType::QJsonArray),
+            48 => Some(QMetaType::QJsonDocument),
+            49 => Some(QMetaType::QByteArrayList),
+            50 => Some(QMetaType::QPersistentModelIndex),
+            51 => Some(QMetaType::Nullptr),
+            52 => Some(QMetaType::QCborSimpleType),
+            53 => Some(QMetaType::QCborValue),
+            54 => Some(QMetaType::QCborArray),
+            55 => Some(QMetaType::QCborMap),
+            64 => Some(QMetaType::QFont),
+            65 => Some(QMetaType::QPixmap),
+            66 => Some(QMetaType::QBrush),
+            67 => Some(QMetaType::QColor),
+            68 => Some(QMetaType::QPalette),
+            69 => Some(QMetaType::QIcon),
+            70 => Some(QMetaType::QImage),
+            71 => Some(QMetaType::QPolygon),
+            72 => Some(QMetaType::QRegion),
+            73 => Some(QMetaType::QBitmap),
+            74 => Some(QMetaType::QCursor),
+            75 => Some(QMetaType::QKeySequence),
+            76 => Some(QMetaType::QPen),
+            77 => Some(QMetaType::QTextLength),
+            78 => Some(QMetaType::QTextFormat),
+            79 => Some(QMetaType::QMatrix),
+            80 => Some(QMetaType::QTransform),
+            81 => Some(QMetaType::QMatrix4x4),
+            82 => Some(QMetaType::QVector2D),
+            83 => Some(QMetaType::QVector3D),
+            84 => Some(QMetaType::QVector4D),
+            85 => Some(QMetaType::QQuaternion),
+            86 => Some(QMetaType::QPolygonF),
+            121 => Some(QMetaType::QSizePolicy),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_id_returns_the_type_with_this_id() {
+        let known: Vec<QMetaType> = (0..QMetaType::User as i32).filter_map(QMetaType::from_id).collect();
+        assert_eq!(known.len(), 80);
+        for t in known {
+            assert_eq!(QMetaType::from_id(t as i32), Some(t));
+        }
+        assert_eq!(QMetaType::from_id(12), Some(QMetaType::QByteArray));
+        assert_eq!(QMetaType::from_id(67), Some(QMetaType::QColor));
+    }
+
+    #[test]
+    fn from_id_rejects_unknown_and_user_ids() {
+        assert_eq!(QMetaType::from_id(-1), None);
+        assert_eq!(QMetaType::from_id(87), None);
+        assert_eq!(QMetaType::from_id(QMetaType::User as i32), None);
+        assert_eq!(QMetaType::from_id(1025), None);
+    }
+
+    #[test]
+    fn small_integers_and_chars_have_their_own_types() {
+        assert_eq!(i8::metatype(), QMetaType::SChar);
+        assert_eq!(i16::metatype(), QMetaType::Short);
+        assert_eq!(u8::metatype(), QMetaType::UChar);
+        assert_eq!(u16::metatype(), QMetaType::UShort);
+        assert_eq!(isize::metatype(), QMetaType::LongLong);
+        assert_eq!(char::metatype(), QMetaType::QString);
+    }
+
+    #[test]
+    fn only_bytes_are_passed_as_byte_arrays() {
+        assert_eq!(Vec::<u8>::metatype(), QMetaType::QByteArray);
+        assert_eq!(Vec::<i8>::metatype(), QMetaType::QVariantList);
+        assert_eq!(Vec::<char>::metatype(), QMetaType::QVariantList);
+        assert_eq!(Vec::<Vec<u8>>::metatype(), QMetaType::QVariantList);
+    }
 }
@@ -1,10 +1,23 @@
 use libc;
-use std::ffi::CStr;
+use std::convert::{Infallible, TryFrom};
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::Deref;
+use std::collections::{BTreeMap, HashMap};
+use std::slice::from_raw_parts;
 use std::sync::atomic::{AtomicPtr, Ordering};
 
 use utils::*;
 use types::*;
 use qobject::*;
+use qmeta::*;
+use qtypes::*;
+use qdatetime::*;
+use qgeometry::*;
+use qcolor::*;
 
 extern "C" {
     fn dos_qvariant_create() -> DosQVariant;
@@ -22,7 +35,9 @@ extern "C" {
     fn dos_qvariant_toString(val: DosQVariant) -> *mut libc::c_char;
     fn dos_qvariant_toFloat(val: DosQVariant) -> f32;
     fn dos_qvariant_toDouble(val: DosQVariant) -> f64;
-    // DOS_API DosQObject *DOS_CALL dos_qvariant_toQObject(const DosQVariant *vptr);
+    fn dos_qvariant_toArray(val: DosQVariant) -> *mut DosQVariantArray;
+    fn dos_qvariant_toQObject(val: DosQVariant) -> DosQObject;
+    fn dos_qvariantarray_delete(ptr: *mut DosQVariantArray);
 
     fn dos_qvariant_isnull(val: DosQVariant) -> bool;
     fn dos_qvariant_assign(val: MutDosQVariant, other: DosQVariant);
@@ -35,33 +50,179 @@ extern "C" {
 // DOS_API void   DOS_CALL dos_qvariant_setString (DosQVariant *vptr, const char *value);
 // DOS_API void   DOS_CALL dos_qvariant_setQObject(DosQVariant *vptr, DosQObject *value);
 
+    fn dosext_qvariant_userType(val: DosQVariant) -> i32;
+    fn dosext_qvariant_typeName(val: DosQVariant) -> *mut libc::c_char;
+    fn dosext_qvariant_isValid(val: DosQVariant) -> bool;
+    fn dosext_qvariant_create_null() -> DosQVariant;
+    fn dosext_qvariant_equal(val: DosQVariant, other: DosQVariant) -> bool;
+    fn dosext_qvariant_create_schar(value: i8) -> DosQVariant;
+    fn dosext_qvariant_create_short(value: i16) -> DosQVariant;
+    fn dosext_qvariant_create_uchar(value: u8) -> DosQVariant;
+    fn dosext_qvariant_create_ushort(value: u16) -> DosQVariant;
+    fn dosext_qvariant_create_uint(value: u32) -> DosQVariant;
+    fn dosext_qvariant_create_longlong(value: i64) -> DosQVariant;
+    fn dosext_qvariant_create_ulonglong(value: u64) -> DosQVariant;
+    fn dosext_qvariant_toUInt(val: DosQVariant) -> u32;
+    fn dosext_qvariant_toLongLong(val: DosQVariant) -> i64;
+    fn dosext_qvariant_toULongLong(val: DosQVariant) -> u64;
+    fn dosext_qvariant_create_bytearray(data: DosCStr, size: i32) -> DosQVariant;
+    fn dosext_qvariant_toByteArray(val: DosQVariant, size: *mut i32) -> DosCStr;
+    fn dosext_qvariant_create_map(size: i32,
+                                  keys: *const DosCStr,
+                                  values: *const DosQVariant)
+                                  -> DosQVariant;
+    fn dosext_qvariant_toMap(val: DosQVariant) -> *mut DosExtQVariantMap;
+    fn dosext_qvariantmap_delete(ptr: *mut DosExtQVariantMap);
+}
+
+#[repr(C)]
+struct DosQVariantArray {
+    size: i32,
+    data: *const DosQVariant,
+}
+
+#[repr(C)]
+struct DosExtQVariantMap {
+    size: i32,
+    keys: *const *mut libc::c_char,
+    values: *const DosQVariant,
+}
+
+/// Contents of a [`QVariant`](struct.QVariant.html), as seen from Rust.
+///
+/// Obtained with [`QVariant::value`](struct.QVariant.html#method.value),
+/// allows to branch on what was actually passed from QML.
+#[derive(Debug)]
+pub enum QVariantValue {
+    /// An empty `QVariant`. `undefined` in QML becomes this.
+    Invalid,
+    /// `null` in QML. An invalid `QDate`, `QTime` or `QDateTime`, like `new Date(NaN)`, becomes this too,
+    /// as does a geometry or color value, that can't be read.
+    Null,
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    LongLong(i64),
+    ULongLong(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// A `QByteArray`, i.e. an `ArrayBuffer` in QML.
+    ByteArray(Vec<u8>),
+    Date(QDate),
+    Time(QTime),
+    DateTime(QDateTime),
+    PointF(QPointF),
+    SizeF(QSizeF),
+    RectF(QRectF),
+    Color(QColor),
+    /// A `QVariantList`, i.e. an array in QML.
+    List(Vec<QVariant>),
+    /// A `QVariantMap`, i.e. a plain object in QML.
+    Map(HashMap<String, QVariant>),
+    /// A pointer to a `QObject`.
+    QObject,
+    /// Any other type. Contains its user type id.
+    Other(i32),
 }
 
 /// This holds a value to be providen for a QML context.
 ///
 /// A value can be different: int, string, float, double, bool or even a custom object.
-#[derive(Debug)]
+///
+/// A `QVariant` always owns its value and deletes it, when dropped.
+/// Values borrowed from Qt are represented by [`QVariantRef`](struct.QVariantRef.html).
+///
+/// Variants are compared by value, the way `QVariant::operator==` does it:
+///
+/// ```
+/// # use qml::*;
+/// assert_eq!(QVariant::from(42), QVariant::from(42));
+/// assert!(QVariant::from("a") != QVariant::from("b"));
+/// ```
 pub struct QVariant {
     ptr: AtomicPtr<WQVariant>,
-    owned: bool,
 }
 
 impl PartialEq for QVariant {
     fn eq(&self, other: &QVariant) -> bool {
-        self.ptr.load(Ordering::Relaxed) == other.ptr.load(Ordering::Relaxed)
+        unsafe { dosext_qvariant_equal(load_self(self), load_self(other)) }
+    }
+}
+
+/// Prints the type name along with the value, e.g. `QVariant(int, 42)`.
+impl fmt::Debug for QVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let type_name = self.type_name();
+        match self.value() {
+            QVariantValue::Invalid => write!(f, "QVariant(Invalid)"),
+            QVariantValue::Null => write!(f, "QVariant({}, null)", type_name),
+            QVariantValue::Bool(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Int(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::UInt(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::LongLong(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::ULongLong(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Float(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Double(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::String(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::ByteArray(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Date(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Time(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::DateTime(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::PointF(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::SizeF(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::RectF(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Color(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::List(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::Map(v) => write!(f, "QVariant({}, {:?})", type_name, v),
+            QVariantValue::QObject |
+            QVariantValue::Other(_) => write!(f, "QVariant({}, {:?})", type_name, qvariant_to_string(self)),
+        }
+    }
+}
+
+/// Uses Qt's conversion to a string, e.g. `42` or `true`.
+///
+/// Types that Qt can't convert, like lists or maps, are printed as an empty string.
+impl fmt::Display for QVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&qvariant_to_string(self))
     }
 }
 
 impl Clone for QVariant {
     fn clone(&self) -> Self {
         unsafe {
-            new_qvar(dos_qvariant_create_qvariant(self.ptr.load(Ordering::Relaxed)),
-                     true)
+            new_qvar(dos_qvariant_create_qvariant(self.ptr.load(Ordering::Relaxed)))
         }
     }
 }
 
+/// Creates an invalid `QVariant`, `undefined` in QML.
+impl Default for QVariant {
+    fn default() -> Self {
+        unsafe { new_qvar(dos_qvariant_create()) }
+    }
+}
+
 impl QVariant {
+    /// Creates a `QVariant` holding `nullptr`, `null` in QML.
+    pub fn null() -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_null()) }
+    }
+
+    /// Returns `true` for an invalid `QVariant` and for a null value, as `QVariant::isNull` does.
+    ///
+    /// Note that Qt also considers some values of other types to be null, e.g. a null `QString`.
+    pub fn is_null(&self) -> bool {
+        unsafe { dos_qvariant_isnull(load_self(self)) }
+    }
+
+    /// Returns `false` for an empty `QVariant`, i.e. `undefined` in QML.
+    pub fn is_valid(&self) -> bool {
+        unsafe { dosext_qvariant_isValid(load_self(self)) }
+    }
+
     pub fn to_int(&self) -> i32 {
         unsafe { dos_qvariant_toInt(self.ptr.load(Ordering::Relaxed)) }
     }
@@ -78,6 +239,124 @@ impl QVariant {
         unsafe { dos_qvariant_toDouble(self.ptr.load(Ordering::Relaxed)) }
     }
 
+    /// Returns the user type id of the stored value.
+    ///
+    /// For an invalid `QVariant` this is `0`.
+    pub fn user_type(&self) -> i32 {
+        unsafe { dosext_qvariant_userType(self.ptr.load(Ordering::Relaxed)) }
+    }
+
+    /// Returns the stored type as a variant of [`QMetaType`](enum.QMetaType.html),
+    /// if the type is known to `qml-rust`.
+    pub fn metatype(&self) -> Option<QMetaType> {
+        QMetaType::from_id(self.user_type())
+    }
+
+    /// Returns the name of the stored type, such as `"int"` or `"QString"`.
+    ///
+    /// For an invalid `QVariant` an empty string is returned.
+    pub fn type_name(&self) -> String {
+        unsafe {
+            let ch_ar = dosext_qvariant_typeName(self.ptr.load(Ordering::Relaxed));
+            let res = CStr::from_ptr(ch_ar)
+                .to_string_lossy()
+                .into_owned();
+            dos_chararray_delete(ch_ar);
+            res
+        }
+    }
+
+    /// Converts this `QVariant` into a Rust value, depending on the stored type.
+    pub fn value(&self) -> QVariantValue {
+        let ptr = self.ptr.load(Ordering::Relaxed);
+        if !self.is_valid() {
+            return QVariantValue::Invalid;
+        }
+        match self.metatype() {
+            Some(QMetaType::Nullptr) => QVariantValue::Null,
+            Some(QMetaType::Bool) => QVariantValue::Bool(unsafe { dos_qvariant_toBool(ptr) }),
+            Some(QMetaType::Int) |
+            Some(QMetaType::Short) |
+            Some(QMetaType::SChar) => QVariantValue::Int(self.to_int()),
+            Some(QMetaType::UInt) |
+            Some(QMetaType::UShort) |
+            Some(QMetaType::UChar) => QVariantValue::UInt(unsafe { dosext_qvariant_toUInt(ptr) }),
+            Some(QMetaType::LongLong) => {
+                QVariantValue::LongLong(unsafe { dosext_qvariant_toLongLong(ptr) })
+            }
+            Some(QMetaType::ULongLong) => {
+                QVariantValue::ULongLong(unsafe { dosext_qvariant_toULongLong(ptr) })
+            }
+            Some(QMetaType::Float) => QVariantValue::Float(unsafe { dos_qvariant_toFloat(ptr) }),
+            Some(QMetaType::Double) => QVariantValue::Double(unsafe { dos_qvariant_toDouble(ptr) }),
+            Some(QMetaType::QString) |
+            Some(QMetaType::QChar) => QVariantValue::String(qvariant_to_string(self)),
+            Some(QMetaType::QByteArray) => QVariantValue::ByteArray(to_bytes(self)),
+            Some(QMetaType::QDate) => self.to_date().map_or(QVariantValue::Null, QVariantValue::Date),
+            Some(QMetaType::QTime) => self.to_time().map_or(QVariantValue::Null, QVariantValue::Time),
+            Some(QMetaType::QDateTime) => {
+                self.to_datetime().map_or(QVariantValue::Null, QVariantValue::DateTime)
+            }
+            Some(QMetaType::QPointF) |
+            Some(QMetaType::QPoint) => self.to_pointf().map_or(QVariantValue::Null, QVariantValue::PointF),
+            Some(QMetaType::QSizeF) |
+            Some(QMetaType::QSize) => self.to_sizef().map_or(QVariantValue::Null, QVariantValue::SizeF),
+            Some(QMetaType::QRectF) |
+            Some(QMetaType::QRect) => self.to_rectf().map_or(QVariantValue::Null, QVariantValue::RectF),
+            Some(QMetaType::QColor) => self.to_color().map_or(QVariantValue::Null, QVariantValue::Color),
+            Some(QMetaType::QVariantList) => QVariantValue::List(to_list(self)),
+            Some(QMetaType::QVariantMap) => QVariantValue::Map(to_map(self)),
+            Some(QMetaType::QObjectStar) => QVariantValue::QObject,
+            _ => QVariantValue::Other(self.user_type()),
+        }
+    }
+
+    /// Returns elements of a `QVariantList`, or `None` if this `QVariant` holds something else.
+    pub fn to_list(&self) -> Option<Vec<QVariant>> {
+        match self.metatype() {
+            Some(QMetaType::QVariantList) => Some(to_list(self)),
+            _ => None,
+        }
+    }
+
+    /// Returns entries of a `QVariantMap`, or `None` if this `QVariant` holds something else.
+    pub fn to_map(&self) -> Option<HashMap<String, QVariant>> {
+        match self.metatype() {
+            Some(QMetaType::QVariantMap) => Some(to_map(self)),
+            _ => None,
+        }
+    }
+
+    /// Returns a `QObject`, if this `QVariant` holds a pointer to one.
+    pub fn to_qobject(&self) -> Option<QObjectRef<'_>> {
+        if !self.is_valid() {
+            return None;
+        }
+        let ptr = unsafe { dos_qvariant_toQObject(load_self(self)) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(new_qobject_ref(ptr))
+        }
+    }
+
+    /// Returns a reference to the Rust wrapper of a `QObject` stored in this `QVariant`.
+    ///
+    /// Succeeds only if the object was created by qml-rust with a [`Q_OBJECT`](macro.Q_OBJECT!.html)
+    /// wrapper of type `T`, e.g. when one of our objects is passed back to a slot from QML.
+    ///
+    /// # Safety
+    ///
+    /// Same as for [`QObjectRef::downcast_ref`](struct.QObjectRef.html#method.downcast_ref):
+    /// the object must not be borrowed mutably or destroyed while the reference is used.
+    pub unsafe fn downcast_ref<T: QObjectMacro + 'static>(&self) -> Option<&T> {
+        let binded_ptr = match self.to_qobject().and_then(|qobj| binded_ptr_of(get_qobject_ref_ptr(&qobj), None)) {
+            Some(binded_ptr) => binded_ptr as *const &mut QObjectMacro,
+            None => return None,
+        };
+        (*binded_ptr).as_any().downcast_ref::<T>()
+    }
+
     /// Sets the value for this `QVariant`
     pub fn set(&mut self, other: &QVariant) {
         unsafe {
@@ -87,64 +366,118 @@ impl QVariant {
     }
 }
 
-pub fn throw(qvar: &mut QVariant, flag: bool) {
-    qvar.owned = flag;
+fn to_list(qvar: &QVariant) -> Vec<QVariant> {
+    unsafe {
+        let array = dos_qvariant_toArray(qvar.ptr.load(Ordering::Relaxed));
+        let res = from_raw_parts((*array).data, (*array).size as usize)
+            .iter()
+            .map(|&ptr| new_qvar(dos_qvariant_create_qvariant(ptr)))
+            .collect();
+        dos_qvariantarray_delete(array);
+        res
+    }
 }
-fn new_qvar(ptr: DosQVariant, owned: bool) -> QVariant {
-    QVariant {
-        ptr: AtomicPtr::new(ptr as MutDosQVariant),
-        owned: owned,
+
+fn to_map(qvar: &QVariant) -> HashMap<String, QVariant> {
+    unsafe {
+        let map = dosext_qvariant_toMap(qvar.ptr.load(Ordering::Relaxed));
+        let size = (*map).size as usize;
+        let keys = from_raw_parts((*map).keys, size);
+        let values = from_raw_parts((*map).values, size);
+        let res = keys.iter()
+            .zip(values.iter())
+            .map(|(&key, &value)| {
+                let key = CStr::from_ptr(key).to_string_lossy().into_owned();
+                (key, new_qvar(dos_qvariant_create_qvariant(value)))
+            })
+            .collect();
+        dosext_qvariantmap_delete(map);
+        res
     }
 }
 
-pub fn new_qvariant(ptr: DosQVariant) -> QVariant {
-    new_qvar(ptr, false)
+pub fn new_qvar(ptr: DosQVariant) -> QVariant {
+    QVariant { ptr: AtomicPtr::new(ptr as MutDosQVariant) }
+}
+
+/// Borrows a `QVariant` owned by Qt, e.g. an argument of a callback.
+pub fn new_qvariant_ref<'a>(ptr: DosQVariant) -> QVariantRef<'a> {
+    QVariantRef {
+        qvar: ManuallyDrop::new(new_qvar(ptr)),
+        phantom: PhantomData,
+    }
 }
 
 pub fn get_private_variant(from: &QVariant) -> DosQVariant {
     from.ptr.load(Ordering::Relaxed)
 }
 
+/// Assigns a value to a `QVariant` owned by Qt, e.g. a result of a callback.
+pub fn assign_qvariant(to: MutDosQVariant, value: &QVariant) {
+    unsafe { dos_qvariant_assign(to, get_private_variant(value)) }
+}
+
 impl Drop for QVariant {
     fn drop(&mut self) {
-        if self.owned {
-            unsafe { dos_qvariant_delete(self.ptr.load(Ordering::Relaxed)) }
-        }
+        unsafe { dos_qvariant_delete(self.ptr.load(Ordering::Relaxed)) }
     }
 }
 
-#[doc(hidden)]
-impl From<DosQObject> for QVariant {
-    fn from(i: DosQObject) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_qobject(i), true) }
+/// A [`QVariant`](struct.QVariant.html) borrowed from Qt, which is valid only for the lifetime `'a`.
+///
+/// Arguments of slots are passed as `QVariantRef`, as they are owned and deleted by Qt
+/// after the call. It dereferences to `&QVariant`, so it can be read and converted as usual,
+/// and it can be cloned into an owned `QVariant` to keep the value around.
+pub struct QVariantRef<'a> {
+    qvar: ManuallyDrop<QVariant>,
+    phantom: PhantomData<&'a QVariant>,
+}
+
+impl<'a> Deref for QVariantRef<'a> {
+    type Target = QVariant;
+
+    fn deref(&self) -> &QVariant {
+        &self.qvar
     }
 }
 
-#[doc(hidden)]
-impl From<DosQAbstractListModel> for QVariant {
-    fn from(i: DosQAbstractListModel) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_qobject(i as DosQObject), true) }
+/// Borrows an owned `QVariant`, e.g. to call a slot from Rust.
+impl<'a> From<&'a QVariant> for QVariantRef<'a> {
+    fn from(i: &'a QVariant) -> Self {
+        new_qvariant_ref(get_private_variant(i))
+    }
+}
+
+impl<'a> fmt::Debug for QVariantRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a> fmt::Display for QVariantRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
     }
 }
 
 #[doc(hidden)]
-impl From<DosQVariant> for QVariant {
-    fn from(vptr: DosQVariant) -> Self {
-        new_qvar(vptr, false)
+impl From<DosQObject> for QVariant {
+    fn from(i: DosQObject) -> Self {
+        unsafe { new_qvar(dos_qvariant_create_qobject(i)) }
     }
 }
 
 #[doc(hidden)]
-impl From<MutDosQVariant> for QVariant {
-    fn from(vptr: MutDosQVariant) -> Self {
-        new_qvar(vptr, false)
+impl From<DosQAbstractListModel> for QVariant {
+    fn from(i: DosQAbstractListModel) -> Self {
+        unsafe { new_qvar(dos_qvariant_create_qobject(i as DosQObject)) }
     }
 }
 
 #[doc(hidden)]
 impl From<QObject> for QVariant {
     fn from(i: QObject) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_qobject(get_qobj_ptr(&i)), true) }
+        unsafe { new_qvar(dos_qvariant_create_qobject(get_qobj_ptr(&i))) }
     }
 }
 
@@ -156,55 +489,145 @@ impl<'a> From<&'a [QVariant]> for QVariant {
                 .collect::<Vec<DosQVariant>>();
             let ptr = vec.as_ptr();
             forget(vec);
-            new_qvar(dos_qvariant_create_array(i.len() as i32, ptr), true)
+            new_qvar(dos_qvariant_create_array(i.len() as i32, ptr))
         }
     }
 }
 
 use std::mem::forget;
 
-impl From<Vec<QVariant>> for QVariant {
-    fn from(i: Vec<QVariant>) -> Self {
-        unsafe {
-            let len = i.len();
-            let vec = i.iter()
-                .map(|qvar| qvar.ptr.load(Ordering::Relaxed) as DosQVariant)
-                .collect::<Vec<DosQVariant>>();
-            let ptr = vec.as_ptr();
-            forget(vec);
-            new_qvar(dos_qvariant_create_array(len as i32, ptr), true)
+/// Produces a `QVariantList`, except for `Vec<u8>`, which produces a `QByteArray`
+impl<T: QMetaTypable + Into<QVariant>> From<Vec<T>> for QVariant {
+    fn from(i: Vec<T>) -> Self {
+        T::list_into_qvariant(i)
+    }
+}
+
+/// Converts a `Vec` into a `QVariantList`.
+pub fn from_vec<T: Into<QVariant>>(i: Vec<T>) -> QVariant {
+    let qvars = i.into_iter().map(Into::into).collect::<Vec<QVariant>>();
+    QVariant::from(qvars.as_slice())
+}
+
+/// `None` becomes `null` in QML
+impl<T: Into<QVariant>> From<Option<T>> for QVariant {
+    fn from(i: Option<T>) -> Self {
+        match i {
+            Some(v) => v.into(),
+            None => QVariant::null(),
         }
     }
 }
 
+fn from_map(entries: Vec<(String, QVariant)>) -> QVariant {
+    let keys = entries.iter()
+        .map(|&(ref key, _)| CString::new(key.as_str()).unwrap())
+        .collect::<Vec<CString>>();
+    let keys_ptrs = keys.iter().map(|key| key.as_ptr()).collect::<Vec<DosCStr>>();
+    let values_ptrs = entries.iter()
+        .map(|&(_, ref value)| load_self(value))
+        .collect::<Vec<DosQVariant>>();
+    unsafe {
+        new_qvar(dosext_qvariant_create_map(entries.len() as i32,
+                                            keys_ptrs.as_ptr(),
+                                            values_ptrs.as_ptr()))
+    }
+}
+
+impl<T: Into<QVariant>> From<HashMap<String, T>> for QVariant {
+    fn from(i: HashMap<String, T>) -> Self {
+        from_map(i.into_iter().map(|(key, value)| (key, value.into())).collect())
+    }
+}
+
+impl<T: Into<QVariant>> From<BTreeMap<String, T>> for QVariant {
+    fn from(i: BTreeMap<String, T>) -> Self {
+        from_map(i.into_iter().map(|(key, value)| (key, value.into())).collect())
+    }
+}
+
 #[doc(hidden)]
 impl<'a> From<&'a QObject> for QVariant {
     fn from(i: &'a QObject) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_qobject(get_qobj_ptr(i)), true) }
+        unsafe { new_qvar(dos_qvariant_create_qobject(get_qobj_ptr(i))) }
+    }
+}
+
+impl From<i8> for QVariant {
+    fn from(i: i8) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_schar(i)) }
+    }
+}
+
+impl From<i16> for QVariant {
+    fn from(i: i16) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_short(i)) }
+    }
+}
+
+impl From<u8> for QVariant {
+    fn from(i: u8) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_uchar(i)) }
+    }
+}
+
+impl From<u16> for QVariant {
+    fn from(i: u16) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_ushort(i)) }
     }
 }
 
 impl From<i32> for QVariant {
     fn from(i: i32) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_int(i), true) }
+        unsafe { new_qvar(dos_qvariant_create_int(i)) }
+    }
+}
+
+impl From<u32> for QVariant {
+    fn from(i: u32) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_uint(i)) }
+    }
+}
+
+impl From<i64> for QVariant {
+    fn from(i: i64) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_longlong(i)) }
+    }
+}
+
+impl From<u64> for QVariant {
+    fn from(i: u64) -> Self {
+        unsafe { new_qvar(dosext_qvariant_create_ulonglong(i)) }
+    }
+}
+
+impl From<usize> for QVariant {
+    fn from(i: usize) -> Self {
+        QVariant::from(i as u64)
+    }
+}
+
+impl From<isize> for QVariant {
+    fn from(i: isize) -> Self {
+        QVariant::from(i as i64)
     }
 }
 
 impl From<f32> for QVariant {
     fn from(i: f32) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_float(i), true) }
+        unsafe { new_qvar(dos_qvariant_create_float(i)) }
     }
 }
 
 impl From<f64> for QVariant {
     fn from(i: f64) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_double(i), true) }
+        unsafe { new_qvar(dos_qvariant_create_double(i)) }
     }
 }
 
 impl From<bool> for QVariant {
     fn from(i: bool) -> Self {
-        unsafe { new_qvar(dos_qvariant_create_bool(i), true) }
+        unsafe { new_qvar(dos_qvariant_create_bool(i)) }
     }
 }
 
@@ -212,7 +635,7 @@ impl<'a> From<&'a str> for QVariant {
     fn from(i: &'a str) -> Self {
         unsafe {
             let ptr = stoptr(i);
-            let qvar = new_qvar(dos_qvariant_create_string(ptr), true);
+            let qvar = new_qvar(dos_qvariant_create_string(ptr));
             // Dropping CString
             ptrtos(ptr);
             qvar
@@ -226,45 +649,537 @@ impl From<String> for QVariant {
     }
 }
 
-// reverse Froms
-impl From<QVariant> for i32 {
-    fn from(i: QVariant) -> Self {
-        unsafe { dos_qvariant_toInt(i.ptr.load(Ordering::Relaxed)) }
+/// Produces a one-character `QString`
+impl From<char> for QVariant {
+    fn from(i: char) -> Self {
+        QVariant::from(i.encode_utf8(&mut [0; 4]) as &str)
     }
 }
 
-impl<'a> From<&'a QVariant> for i32 {
-    fn from(i: &'a QVariant) -> Self {
-        i.to_int()
+/// Produces a `QByteArray`
+impl<'a> From<&'a [u8]> for QVariant {
+    fn from(i: &'a [u8]) -> Self {
+        unsafe {
+            new_qvar(dosext_qvariant_create_bytearray(i.as_ptr() as DosCStr, i.len() as i32))
+        }
     }
 }
 
-impl From<QVariant> for String {
-    fn from(i: QVariant) -> Self {
-        unsafe {
-            let ch_ar = dos_qvariant_toString(load_self(&i));
-            let res = CStr::from_ptr(ch_ar)
-                .to_string_lossy()
-                .into_owned();
-            dos_chararray_delete(ch_ar);
-            res
+/// A single byte is passed as `uchar`, i.e. a number in JavaScript, but a `Vec<u8>` is passed as `QByteArray`.
+impl QMetaTypable for u8 {
+    fn metatype() -> QMetaType {
+        QMetaType::UChar
+    }
+
+    fn list_metatype() -> QMetaType {
+        QMetaType::QByteArray
+    }
+
+    fn list_into_qvariant(list: Vec<u8>) -> QVariant {
+        QVariant::from(list.as_slice())
+    }
+
+    fn list_from_qvariant(i: &QVariant) -> Result<Vec<u8>, QVariantConversionError> {
+        match i.metatype() {
+            Some(QMetaType::QByteArray) => Ok(to_bytes(i)),
+            _ => Err(QVariantConversionError::mismatch("QByteArray", i)),
         }
     }
 }
 
-impl<'a> From<&'a QVariant> for String {
-    fn from(i: &'a QVariant) -> Self {
-        unsafe {
-            let ch_ar = dos_qvariant_toString(load_self(i));
-            let res = CStr::from_ptr(ch_ar)
-                .to_string_lossy()
-                .into_owned();
-            dos_chararray_delete(ch_ar);
-            res
+/// An error returned when a [`QVariant`](struct.QVariant.html) can't be converted into a Rust type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QVariantConversionError {
+    /// The `QVariant` holds a value of another type.
+    TypeMismatch {
+        /// Name of the Qt type, that was expected
+        expected: &'static str,
+        /// Name of the Qt type, that is actually stored
+        actual: String,
+    },
+    /// The `QVariant` holds a number or a date, that doesn't fit into the requested type.
+    OutOfRange {
+        /// Name of the Rust type, that was requested
+        expected: &'static str,
+        /// The stored value
+        value: String,
+    },
+    /// A `QVariantMap` lacks a key, that is required to build a struct.
+    MissingKey {
+        /// Name of the Rust type, that was requested
+        expected: &'static str,
+        /// The missing key
+        key: String,
+    },
+}
+
+impl QVariantConversionError {
+    pub(crate) fn mismatch(expected: &'static str, qvar: &QVariant) -> Self {
+        QVariantConversionError::TypeMismatch {
+            expected: expected,
+            actual: qvar.type_name(),
+        }
+    }
+}
+
+impl fmt::Display for QVariantConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QVariantConversionError::TypeMismatch { expected, ref actual } if actual.is_empty() => {
+                write!(f, "expected a QVariant of type `{}`, found an invalid QVariant", expected)
+            }
+            QVariantConversionError::TypeMismatch { expected, ref actual } => {
+                write!(f, "expected a QVariant of type `{}`, found `{}`", expected, actual)
+            }
+            QVariantConversionError::OutOfRange { expected, ref value } => {
+                write!(f, "{} is out of range for `{}`", value, expected)
+            }
+            QVariantConversionError::MissingKey { expected, ref key } => {
+                write!(f, "key `{}` of `{}` is missing", key, expected)
+            }
+        }
+    }
+}
+
+impl Error for QVariantConversionError {}
+
+impl From<Infallible> for QVariantConversionError {
+    fn from(i: Infallible) -> Self {
+        match i {}
+    }
+}
+
+// reverse conversions
+/// Returns an integral value stored in a `QVariant` of any numeric type.
+///
+/// Doubles are accepted as long as they don't have a fractional part, as all numbers coming from JavaScript are doubles.
+pub fn qvariant_to_integer(i: &QVariant) -> Option<i128> {
+    value_to_integer(i.value())
+}
+
+fn value_to_integer(value: QVariantValue) -> Option<i128> {
+    match value {
+        QVariantValue::Int(v) => Some(v as i128),
+        QVariantValue::UInt(v) => Some(v as i128),
+        QVariantValue::LongLong(v) => Some(v as i128),
+        QVariantValue::ULongLong(v) => Some(v as i128),
+        QVariantValue::Double(v) if v.fract() == 0.0 => Some(v as i128),
+        QVariantValue::Float(v) if v.fract() == 0.0 => Some(v as i128),
+        _ => None,
+    }
+}
+
+fn integer_in_range<T: TryFrom<i128>>(v: i128, expected: &'static str) -> Result<T, QVariantConversionError> {
+    T::try_from(v).map_err(|_| {
+        QVariantConversionError::OutOfRange {
+            expected: expected,
+            value: v.to_string(),
+        }
+    })
+}
+
+macro_rules! integer_conversions {
+    ($($t:ty => $qt:expr,)*) => {
+        $(
+            impl<'a> TryFrom<&'a QVariant> for $t {
+                type Error = QVariantConversionError;
+
+                fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+                    match qvariant_to_integer(i) {
+                        Some(v) => integer_in_range(v, stringify!($t)),
+                        None => Err(QVariantConversionError::mismatch($qt, i)),
+                    }
+                }
+            }
+
+            impl TryFrom<QVariant> for $t {
+                type Error = QVariantConversionError;
+
+                fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+                    <$t>::try_from(&i)
+                }
+            }
+        )*
+    }
+}
+
+integer_conversions! {
+    i8 => "signed char",
+    i16 => "short",
+    u8 => "uchar",
+    u16 => "ushort",
+    i32 => "int",
+    u32 => "uint",
+    i64 => "qlonglong",
+    u64 => "qulonglong",
+    isize => "qlonglong",
+    usize => "qulonglong",
+}
+
+impl<'a> TryFrom<&'a QVariant> for f64 {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.value() {
+            QVariantValue::Double(v) => Ok(v),
+            QVariantValue::Float(v) => Ok(v as f64),
+            QVariantValue::Int(v) => Ok(v as f64),
+            QVariantValue::UInt(v) => Ok(v as f64),
+            QVariantValue::LongLong(v) => Ok(v as f64),
+            QVariantValue::ULongLong(v) => Ok(v as f64),
+            _ => Err(QVariantConversionError::mismatch("double", i)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for f32 {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.value() {
+            QVariantValue::Float(v) => Ok(v),
+            QVariantValue::Double(v) => Ok(v as f32),
+            QVariantValue::Int(v) => Ok(v as f32),
+            QVariantValue::UInt(v) => Ok(v as f32),
+            QVariantValue::LongLong(v) => Ok(v as f32),
+            QVariantValue::ULongLong(v) => Ok(v as f32),
+            _ => Err(QVariantConversionError::mismatch("float", i)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for bool {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.value() {
+            QVariantValue::Bool(v) => Ok(v),
+            _ => Err(QVariantConversionError::mismatch("bool", i)),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for String {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.value() {
+            QVariantValue::String(v) => Ok(v),
+            _ => Err(QVariantConversionError::mismatch("QString", i)),
+        }
+    }
+}
+
+/// Accepts a `QString` or a `QChar` of exactly one character
+impl<'a> TryFrom<&'a QVariant> for char {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.value() {
+            QVariantValue::String(v) => string_to_char(v),
+            _ => Err(QVariantConversionError::mismatch("QString", i)),
+        }
+    }
+}
+
+fn string_to_char(s: String) -> Result<char, QVariantConversionError> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => {
+            Err(QVariantConversionError::OutOfRange {
+                expected: "char",
+                value: s,
+            })
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a QVariant> for QVariant {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        Ok(i.clone())
+    }
+}
+
+/// Both `null` and `undefined` become `None`
+impl<'a, T> TryFrom<&'a QVariant> for Option<T>
+    where T: TryFrom<&'a QVariant, Error = QVariantConversionError>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        if !i.is_valid() || i.metatype() == Some(QMetaType::Nullptr) {
+            Ok(None)
+        } else {
+            T::try_from(i).map(Some)
+        }
+    }
+}
+
+impl TryFrom<QVariant> for f64 {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        f64::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for f32 {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        f32::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for bool {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        bool::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for String {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        String::try_from(&i)
+    }
+}
+
+impl TryFrom<QVariant> for char {
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        char::try_from(&i)
+    }
+}
+
+impl<T> TryFrom<QVariant> for Option<T>
+    where T: TryFrom<QVariant, Error = QVariantConversionError>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        if !i.is_valid() || i.metatype() == Some(QMetaType::Nullptr) {
+            Ok(None)
+        } else {
+            T::try_from(i).map(Some)
+        }
+    }
+}
+
+impl<T> TryFrom<QVariant> for Vec<T>
+    where T: QMetaTypable + TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        Vec::try_from(&i)
+    }
+}
+
+/// Accepts a `QVariantList`, or a `QByteArray` for `Vec<u8>`
+impl<'a, T> TryFrom<&'a QVariant> for Vec<T>
+    where T: QMetaTypable + TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        T::list_from_qvariant(i)
+    }
+}
+
+/// Converts a `QVariantList` into a `Vec`.
+pub fn vec_from_qvariant<T>(i: &QVariant) -> Result<Vec<T>, QVariantConversionError>
+    where T: TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    match i.to_list() {
+        Some(list) => {
+            list.into_iter()
+                .map(|qvar| T::try_from(qvar).map_err(From::from))
+                .collect()
+        }
+        None => Err(QVariantConversionError::mismatch("QVariantList", i)),
+    }
+}
+
+impl<T> TryFrom<QVariant> for HashMap<String, T>
+    where T: TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        HashMap::try_from(&i)
+    }
+}
+
+impl<'a, T> TryFrom<&'a QVariant> for HashMap<String, T>
+    where T: TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.to_map() {
+            Some(map) => {
+                map.into_iter()
+                    .map(|(key, qvar)| T::try_from(qvar).map(|v| (key, v)).map_err(From::from))
+                    .collect()
+            }
+            None => Err(QVariantConversionError::mismatch("QVariantMap", i)),
         }
     }
 }
 
+impl<T> TryFrom<QVariant> for BTreeMap<String, T>
+    where T: TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: QVariant) -> Result<Self, Self::Error> {
+        BTreeMap::try_from(&i)
+    }
+}
+
+impl<'a, T> TryFrom<&'a QVariant> for BTreeMap<String, T>
+    where T: TryFrom<QVariant>,
+          QVariantConversionError: From<T::Error>
+{
+    type Error = QVariantConversionError;
+
+    fn try_from(i: &'a QVariant) -> Result<Self, Self::Error> {
+        match i.to_map() {
+            Some(map) => {
+                map.into_iter()
+                    .map(|(key, qvar)| T::try_from(qvar).map(|v| (key, v)).map_err(From::from))
+                    .collect()
+            }
+            None => Err(QVariantConversionError::mismatch("QVariantMap", i)),
+        }
+    }
+}
+
+/// Copies the contents of a `QVariant`, that holds a `QByteArray`.
+fn to_bytes(i: &QVariant) -> Vec<u8> {
+    unsafe {
+        let mut size = 0;
+        let data = dosext_qvariant_toByteArray(load_self(i), &mut size);
+        let res = from_raw_parts(data as *const u8, size as usize).to_vec();
+        dos_chararray_delete(data);
+        res
+    }
+}
+
+/// Converts any `QVariant` to a string the way Qt does, without checking its type.
+pub fn qvariant_to_string(i: &QVariant) -> String {
+    unsafe {
+        let ch_ar = dos_qvariant_toString(load_self(i));
+        let res = CStr::from_ptr(ch_ar)
+            .to_string_lossy()
+            .into_owned();
+        dos_chararray_delete(ch_ar);
+        res
+    }
+}
+
 fn load_self(s: &QVariant) -> DosQVariant {
     s.ptr.load(Ordering::Relaxed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_read_from_any_numeric_value() {
+        assert_eq!(value_to_integer(QVariantValue::Int(-3)), Some(-3));
+        assert_eq!(value_to_integer(QVariantValue::UInt(3)), Some(3));
+        assert_eq!(value_to_integer(QVariantValue::LongLong(i64::min_value())),
+                   Some(i64::min_value() as i128));
+        assert_eq!(value_to_integer(QVariantValue::ULongLong(u64::max_value())),
+                   Some(u64::max_value() as i128));
+        assert_eq!(value_to_integer(QVariantValue::Double(42.0)), Some(42));
+        assert_eq!(value_to_integer(QVariantValue::Float(-1.0)), Some(-1));
+    }
+
+    #[test]
+    fn fractions_and_other_types_are_not_integers() {
+        assert_eq!(value_to_integer(QVariantValue::Double(0.5)), None);
+        assert_eq!(value_to_integer(QVariantValue::Float(2.25)), None);
+        assert_eq!(value_to_integer(QVariantValue::Bool(true)), None);
+        assert_eq!(value_to_integer(QVariantValue::String("1".into())), None);
+        assert_eq!(value_to_integer(QVariantValue::Invalid), None);
+    }
+
+    #[test]
+    fn integers_are_checked_for_range() {
+        assert_eq!(integer_in_range::<i32>(i32::max_value() as i128, "i32"), Ok(i32::max_value()));
+        assert_eq!(integer_in_range::<u32>(-1, "u32"),
+                   Err(QVariantConversionError::OutOfRange {
+                       expected: "u32",
+                       value: "-1".into(),
+                   }));
+        assert!(integer_in_range::<i32>(i32::max_value() as i128 + 1, "i32").is_err());
+        assert!(integer_in_range::<u64>(u64::max_value() as i128, "u64").is_ok());
+    }
+
+    #[test]
+    fn small_integers_are_checked_for_range() {
+        assert_eq!(integer_in_range::<i8>(-128, "i8"), Ok(-128));
+        assert!(integer_in_range::<i8>(128, "i8").is_err());
+        assert_eq!(integer_in_range::<u8>(255, "u8"), Ok(255));
+        assert_eq!(integer_in_range::<u8>(256, "u8"),
+                   Err(QVariantConversionError::OutOfRange {
+                       expected: "u8",
+                       value: "256".into(),
+                   }));
+        assert!(integer_in_range::<i16>(-32769, "i16").is_err());
+        assert!(integer_in_range::<u16>(-1, "u16").is_err());
+        assert_eq!(integer_in_range::<isize>(-1, "isize"), Ok(-1));
+    }
+
+    #[test]
+    fn chars_are_read_from_one_character_strings() {
+        assert_eq!(string_to_char("ж".into()), Ok('ж'));
+        assert_eq!(string_to_char("🦀".into()), Ok('🦀'));
+        assert_eq!(string_to_char("ab".into()),
+                   Err(QVariantConversionError::OutOfRange {
+                       expected: "char",
+                       value: "ab".into(),
+                   }));
+        assert!(string_to_char(String::new()).is_err());
+    }
+
+    #[test]
+    fn conversion_errors_are_displayed() {
+        let mismatch = QVariantConversionError::TypeMismatch {
+            expected: "int",
+            actual: "QString".into(),
+        };
+        assert_eq!(mismatch.to_string(), "expected a QVariant of type `int`, found `QString`");
+        let invalid = QVariantConversionError::TypeMismatch {
+            expected: "int",
+            actual: String::new(),
+        };
+        assert_eq!(invalid.to_string(), "expected a QVariant of type `int`, found an invalid QVariant");
+        let range = QVariantConversionError::OutOfRange {
+            expected: "u32",
+            value: "-1".into(),
+        };
+        assert_eq!(range.to_string(), "-1 is out of range for `u32`");
+        let missing = QVariantConversionError::MissingKey {
+            expected: "Point",
+            key: "x".into(),
+        };
+        assert_eq!(missing.to_string(), "key `x` of `Point` is missing");
+    }
+}
@@ -10,6 +10,7 @@ pub type MutDosQHashIntQByteArray = *mut WQHashIntQByteArray;
 pub type DosQMetaObject = *const WQMetaObject;
 pub type DosQAbstractListModel = *mut WQAbstractListModel;
 pub type DosQUrl = *mut WQUrl;
+pub type DosQMetaObjectConnection = *mut WQMetaObjectConnection;
 
 pub type DosCStr = *const libc::c_char;
 
@@ -22,3 +23,4 @@ pub enum WQHashIntQByteArray {}
 pub enum WQMetaObject {}
 pub enum WQAbstractListModel {}
 pub enum WQUrl {}
+pub enum WQMetaObjectConnection {}
@@ -0,0 +1,9 @@
+extern crate trybuild;
+
+/// Checks, that `#[qobject]` expands to code, that compiles, and that wrong types are reported where they are declared.
+#[test]
+fn qobject_attribute() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/qobject_attribute.rs");
+    t.compile_fail("tests/ui/qobject_wrong_type.rs");
+}
@@ -0,0 +1,37 @@
+extern crate qml;
+
+use qml::*;
+
+pub struct Counter {
+    count: i32,
+    step: i32,
+}
+
+#[qobject(Counter)]
+impl QCounter {
+    #[qsignal]
+    fn overflow(&self, at: i32);
+
+    #[qslot]
+    fn increment(&mut self) -> i32 {
+        let count = self.count + self.step;
+        self.set_count(count);
+        self.count
+    }
+
+    #[qslot]
+    fn parse(&self, input: String) -> Result<i32, std::num::ParseIntError> {
+        input.parse()
+    }
+
+    #[qmember(write, notify)]
+    fn count(&self) -> i32;
+
+    #[qmember]
+    fn step(&self) -> i32;
+
+    #[qproperty]
+    fn title(&self) -> String;
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+extern crate qml;
+
+use qml::*;
+
+pub struct Counter;
+
+pub struct Step(i32);
+
+#[qobject(Counter)]
+impl QCounter {
+    #[qslot]
+    fn advance(&mut self, _step: Step) {}
+}
+
+fn main() {}